@@ -0,0 +1,230 @@
+//! Transport abstraction between the request loop in `main.rs` and wherever
+//! `lode.runner` actually executes. `LocalBackend` spawns it as a subprocess
+//! and pipes the newline-delimited `Request`/`Response`/`Interrupt` JSON
+//! (see `protocol.rs`) over its stdin/stdout; `RemoteBackend` tunnels the
+//! same protocol over a plain TCP connection to a worker on another host
+//! (point it at an SSH local-forward to run over a secure tunnel). Both are
+//! wrapped in `AnyBackend` so `--backend local|remote` can pick one at
+//! runtime while `run_research_query`/`run_single_query` stay generic over
+//! the `Backend` trait and don't care which transport they're driving.
+
+use std::io;
+use std::process::Stdio;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+use crate::cli::BackendTarget;
+
+/// A transport that can be split into an independent write half (for the
+/// initial request, clarifying answers, and interrupts) and read half (for
+/// streaming `Response` JSON lines), so an interrupt can reach the backend
+/// while a response is still being awaited.
+pub trait Backend: Send {
+    type Writer: BackendWriter;
+    type Reader: BackendReader;
+
+    fn split(self) -> (Self::Writer, Self::Reader);
+}
+
+/// Write half of a `Backend`.
+pub trait BackendWriter: Send {
+    /// Writes `line` (a serialized `Request`/`ClarifyingAnswers`/`Interrupt`)
+    /// followed by a newline.
+    async fn send_line(&mut self, line: &str) -> io::Result<()>;
+}
+
+/// Read half of a `Backend`, yielding newline-delimited `Response` JSON.
+pub trait BackendReader: Send {
+    /// Reads the next line, or `None` at EOF.
+    async fn next_line(&mut self) -> io::Result<Option<String>>;
+
+    /// Whether the transport itself completed cleanly, independent of what
+    /// the JSON protocol's own `Response::Done` reported. Defaults to
+    /// `true`; `LocalReader` overrides this to check the subprocess's exit
+    /// status, since a remote worker has no local process to wait on.
+    async fn finished_successfully(&mut self) -> io::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Runs `lode.runner` as a local subprocess.
+pub struct LocalBackend {
+    child: Child,
+    stdin: ChildStdin,
+}
+
+impl LocalBackend {
+    pub fn spawn() -> io::Result<Self> {
+        let mut child = Command::new("uv")
+            .args(["run", "python", "-m", "lode.runner"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("Failed to open stdin");
+        Ok(Self { child, stdin })
+    }
+}
+
+impl Backend for LocalBackend {
+    type Writer = LocalWriter;
+    type Reader = LocalReader;
+
+    fn split(mut self) -> (Self::Writer, Self::Reader) {
+        let stdout = self.child.stdout.take().expect("Failed to open stdout");
+        (
+            LocalWriter { stdin: self.stdin },
+            LocalReader {
+                child: self.child,
+                lines: BufReader::new(stdout).lines(),
+            },
+        )
+    }
+}
+
+pub struct LocalWriter {
+    stdin: ChildStdin,
+}
+
+impl BackendWriter for LocalWriter {
+    async fn send_line(&mut self, line: &str) -> io::Result<()> {
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.write_all(b"\n").await
+    }
+}
+
+pub struct LocalReader {
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+impl BackendReader for LocalReader {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        self.lines.next_line().await
+    }
+
+    async fn finished_successfully(&mut self) -> io::Result<bool> {
+        Ok(self.child.wait().await?.success())
+    }
+}
+
+/// Runs `lode.runner` on another host, tunneling the protocol over TCP.
+pub struct RemoteBackend {
+    stream: TcpStream,
+}
+
+impl RemoteBackend {
+    pub async fn connect(addr: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self { stream })
+    }
+}
+
+impl Backend for RemoteBackend {
+    type Writer = RemoteWriter;
+    type Reader = RemoteReader;
+
+    fn split(self) -> (Self::Writer, Self::Reader) {
+        let (read_half, write_half) = self.stream.into_split();
+        (
+            RemoteWriter { writer: write_half },
+            RemoteReader {
+                lines: BufReader::new(read_half).lines(),
+            },
+        )
+    }
+}
+
+pub struct RemoteWriter {
+    writer: OwnedWriteHalf,
+}
+
+impl BackendWriter for RemoteWriter {
+    async fn send_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes()).await?;
+        self.writer.write_all(b"\n").await
+    }
+}
+
+pub struct RemoteReader {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+}
+
+impl BackendReader for RemoteReader {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        self.lines.next_line().await
+    }
+}
+
+/// Either transport, chosen at runtime by `--backend`/`LODE_BACKEND` so
+/// `run_research_query`/`run_single_query` can stay generic over `Backend`
+/// without the caller needing to match on `BackendTarget` itself.
+pub enum AnyBackend {
+    Local(LocalBackend),
+    Remote(RemoteBackend),
+}
+
+impl AnyBackend {
+    pub async fn connect(target: &BackendTarget) -> io::Result<Self> {
+        match target {
+            BackendTarget::Local => Ok(Self::Local(LocalBackend::spawn()?)),
+            BackendTarget::Remote(addr) => Ok(Self::Remote(RemoteBackend::connect(addr).await?)),
+        }
+    }
+}
+
+pub enum AnyWriter {
+    Local(LocalWriter),
+    Remote(RemoteWriter),
+}
+
+pub enum AnyReader {
+    Local(LocalReader),
+    Remote(RemoteReader),
+}
+
+impl Backend for AnyBackend {
+    type Writer = AnyWriter;
+    type Reader = AnyReader;
+
+    fn split(self) -> (Self::Writer, Self::Reader) {
+        match self {
+            Self::Local(backend) => {
+                let (writer, reader) = backend.split();
+                (AnyWriter::Local(writer), AnyReader::Local(reader))
+            }
+            Self::Remote(backend) => {
+                let (writer, reader) = backend.split();
+                (AnyWriter::Remote(writer), AnyReader::Remote(reader))
+            }
+        }
+    }
+}
+
+impl BackendWriter for AnyWriter {
+    async fn send_line(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            Self::Local(writer) => writer.send_line(line).await,
+            Self::Remote(writer) => writer.send_line(line).await,
+        }
+    }
+}
+
+impl BackendReader for AnyReader {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        match self {
+            Self::Local(reader) => reader.next_line().await,
+            Self::Remote(reader) => reader.next_line().await,
+        }
+    }
+
+    async fn finished_successfully(&mut self) -> io::Result<bool> {
+        match self {
+            Self::Local(reader) => reader.finished_successfully().await,
+            Self::Remote(reader) => reader.finished_successfully().await,
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! `--batch <file>` evaluation harness: runs every query in the file
+//! concurrently against the backend (bounded by `--concurrency`) and
+//! reports aggregate latency/token stats (searches used are recorded per
+//! query in the `--json` records, not aggregated), so a model or
+//! `search_count` change can be compared across a whole query set instead
+//! of eyeballing one run at a time.
+
+use std::error::Error;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::backend::{AnyBackend, Backend, BackendReader, BackendWriter};
+use crate::cli::{self, Cli, RequestConfig};
+use crate::protocol::{Request, Response};
+use crate::run::{
+    RunContext, append_response, setup_run_directory, write_metadata, write_output, write_request,
+};
+
+/// Outcome of running one `--batch` query to completion.
+#[derive(Clone, Serialize)]
+pub struct BenchRecord {
+    pub query: String,
+    pub run_id: String,
+    pub success: bool,
+    pub latency_ms: u64,
+    pub total_tokens: Option<u32>,
+    pub searches_used: Option<u32>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Percentiles {
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    mean: f64,
+    max: f64,
+}
+
+#[derive(Serialize)]
+struct Aggregate {
+    count: usize,
+    success_count: usize,
+    success_rate: f64,
+    latency_ms: Percentiles,
+    total_tokens: Percentiles,
+}
+
+#[derive(Serialize)]
+struct BenchReport<'a> {
+    records: &'a [BenchRecord],
+    aggregate: Aggregate,
+}
+
+/// Parses `--batch` input as a JSON array of strings if it looks like one,
+/// otherwise as one query per non-empty, non-`#comment` line.
+pub fn parse_queries(raw: &str) -> Vec<String> {
+    let trimmed = raw.trim_start();
+    if trimmed.starts_with('[') {
+        if let Ok(queries) = serde_json::from_str::<Vec<String>>(trimmed) {
+            return queries;
+        }
+    }
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Runs every query in `batch_path` against `cli`'s configured backend and
+/// prints an aggregate report. Errors from an individual query are folded
+/// into its `BenchRecord` rather than aborting the batch.
+pub async fn run_batch(cli: &Cli, batch_path: &str) -> Result<(), Box<dyn Error>> {
+    let raw = tokio::fs::read_to_string(batch_path).await?;
+    let queries = parse_queries(&raw);
+    if queries.is_empty() {
+        return Err(format!("no queries found in {}", batch_path).into());
+    }
+
+    let config = cli::load_config(cli);
+    let backend_target = cli::backend_target(cli);
+    let concurrency = cli
+        .concurrency
+        .unwrap_or(cli::DEFAULT_BATCH_CONCURRENCY)
+        .max(1) as usize;
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let total = queries.len();
+    let json_mode = cli.json;
+
+    if !json_mode {
+        eprintln!("Running {} queries (concurrency {})", total, concurrency);
+    }
+
+    let mut handles = Vec::with_capacity(total);
+    for (index, query) in queries.into_iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let config = config.clone();
+        let backend_target = backend_target.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("bench semaphore closed early");
+            let record = run_one(&query, &config, &backend_target).await;
+            if !json_mode {
+                eprintln!(
+                    "[{}/{}] {} - {} ({} ms)",
+                    index + 1,
+                    total,
+                    if record.success { "ok" } else { "FAIL" },
+                    record.query,
+                    record.latency_ms,
+                );
+            }
+            record
+        }));
+    }
+
+    let mut records = Vec::with_capacity(handles.len());
+    for handle in handles {
+        records.push(handle.await.expect("bench task panicked"));
+    }
+
+    let aggregate = aggregate(&records);
+
+    if json_mode {
+        let report = BenchReport {
+            records: &records,
+            aggregate,
+        };
+        println!("{}", serde_json::to_string(&report)?);
+    } else {
+        print_summary(&records, &aggregate);
+    }
+
+    Ok(())
+}
+
+/// Runs a single query end-to-end over a fresh backend connection, writing
+/// `request.json`, the `responses.jsonl` transcript, `metadata.json` and
+/// `output.md` under `runs/<id>/` so a batch run can still be inspected
+/// query-by-query afterward. Prompts, raw responses, tool calls and
+/// `events.ndjson` are not written.
+async fn run_one(
+    query: &str,
+    config: &RequestConfig,
+    backend_target: &cli::BackendTarget,
+) -> BenchRecord {
+    let run_id = Uuid::new_v4().to_string();
+    let request = Request {
+        version: "v1",
+        run_id: run_id.clone(),
+        query: query.to_string(),
+        config: config.clone(),
+    };
+
+    match run_one_inner(&request, backend_target).await {
+        Ok((ctx, success, searches_used)) => {
+            let latency_ms = ctx.elapsed_ms();
+            let total_tokens = ctx.total_tokens;
+            let metadata = ctx.to_metadata(run_id.clone());
+            let _ = write_metadata(&ctx.run_dir, &metadata).await;
+            if let Some(ref markdown) = ctx.markdown_report {
+                let _ = write_output(&ctx.run_dir, markdown).await;
+            }
+            BenchRecord {
+                query: query.to_string(),
+                run_id,
+                success,
+                latency_ms,
+                total_tokens,
+                searches_used,
+                error: None,
+            }
+        }
+        Err(e) => BenchRecord {
+            query: query.to_string(),
+            run_id,
+            success: false,
+            latency_ms: 0,
+            total_tokens: None,
+            searches_used: None,
+            error: Some(e),
+        },
+    }
+}
+
+async fn run_one_inner(
+    request: &Request,
+    backend_target: &cli::BackendTarget,
+) -> Result<(RunContext, bool, Option<u32>), String> {
+    let run_dir = setup_run_directory(&request.run_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    write_request(&run_dir, request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut ctx = RunContext::new(run_dir);
+
+    let backend = AnyBackend::connect(backend_target)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (mut writer, mut reader) = backend.split();
+    let request_json = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    writer.send_line(&request_json).await.map_err(|e| e.to_string())?;
+
+    let mut success = false;
+    let mut remaining_searches = None;
+    let max_searches = request.config.max_searches;
+
+    while let Some(line) = reader.next_line().await.map_err(|e| e.to_string())? {
+        let Ok(response) = serde_json::from_str::<Response>(&line) else {
+            continue;
+        };
+        let _ = append_response(&ctx.run_dir, &line).await;
+        match response {
+            Response::Decision {
+                remaining_searches: r,
+                ..
+            } => {
+                remaining_searches = Some(r);
+            }
+            Response::Report {
+                markdown_report, ..
+            } => {
+                ctx.markdown_report = Some(markdown_report);
+            }
+            Response::Metadata {
+                model,
+                total_tokens,
+                ..
+            } => {
+                ctx.model = Some(model);
+                ctx.total_tokens = total_tokens;
+            }
+            Response::Done { success: s } => {
+                success = s;
+            }
+            _ => {}
+        }
+    }
+
+    drop(writer);
+    let transport_ok = reader.finished_successfully().await.map_err(|e| e.to_string())?;
+    let searches_used = remaining_searches.map(|r| max_searches.saturating_sub(r));
+
+    Ok((ctx, success && transport_ok, searches_used))
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let n = sorted.len();
+    let idx = ((p / 100.0) * n as f64).ceil() as usize;
+    let idx = idx.saturating_sub(1).min(n - 1);
+    sorted[idx]
+}
+
+fn stats(mut samples: Vec<f64>) -> Percentiles {
+    if samples.is_empty() {
+        return Percentiles {
+            p50: 0.0,
+            p90: 0.0,
+            p99: 0.0,
+            mean: 0.0,
+            max: 0.0,
+        };
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    let max = samples[samples.len() - 1];
+    Percentiles {
+        p50: percentile(&samples, 50.0),
+        p90: percentile(&samples, 90.0),
+        p99: percentile(&samples, 99.0),
+        mean,
+        max,
+    }
+}
+
+fn aggregate(records: &[BenchRecord]) -> Aggregate {
+    let count = records.len();
+    let success_count = records.iter().filter(|r| r.success).count();
+    let latency_samples = records.iter().map(|r| r.latency_ms as f64).collect();
+    let token_samples = records
+        .iter()
+        .filter_map(|r| r.total_tokens)
+        .map(|t| t as f64)
+        .collect();
+
+    Aggregate {
+        count,
+        success_count,
+        success_rate: if count == 0 {
+            0.0
+        } else {
+            success_count as f64 / count as f64
+        },
+        latency_ms: stats(latency_samples),
+        total_tokens: stats(token_samples),
+    }
+}
+
+fn print_summary(records: &[BenchRecord], aggregate: &Aggregate) {
+    println!();
+    println!(
+        "{}/{} succeeded ({:.1}%)",
+        aggregate.success_count,
+        aggregate.count,
+        aggregate.success_rate * 100.0
+    );
+    println!(
+        "latency ms: p50={:.0} p90={:.0} p99={:.0} mean={:.0} max={:.0}",
+        aggregate.latency_ms.p50,
+        aggregate.latency_ms.p90,
+        aggregate.latency_ms.p99,
+        aggregate.latency_ms.mean,
+        aggregate.latency_ms.max,
+    );
+    println!(
+        "total_tokens: p50={:.0} p90={:.0} p99={:.0} mean={:.0} max={:.0}",
+        aggregate.total_tokens.p50,
+        aggregate.total_tokens.p90,
+        aggregate.total_tokens.p99,
+        aggregate.total_tokens.mean,
+        aggregate.total_tokens.max,
+    );
+    for record in records.iter().filter(|r| !r.success) {
+        println!(
+            "  FAILED: {} ({})",
+            record.query,
+            record.error.as_deref().unwrap_or("no report")
+        );
+    }
+}
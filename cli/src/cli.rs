@@ -3,10 +3,14 @@ use std::env;
 use clap::Parser;
 use serde::Serialize;
 
+use crate::export::ExportFormat;
+
 pub const DEFAULT_MODEL: &str = "gpt-4o";
 pub const DEFAULT_SEARCH_COUNT: u32 = 5;
 pub const DEFAULT_MAX_ITERATIONS: u32 = 10;
 pub const DEFAULT_MAX_SEARCHES: u32 = 15;
+pub const DEFAULT_BACKEND_ADDR: &str = "127.0.0.1:4269";
+pub const DEFAULT_BATCH_CONCURRENCY: u32 = 4;
 
 #[derive(Parser)]
 #[command(name = "lode")]
@@ -42,6 +46,172 @@ pub struct Cli {
     /// Suppress progress output, only emit final result and errors
     #[arg(long, short)]
     pub quiet: bool,
+
+    /// Log verbosity for the on-disk tracing log (overrides LODE_LOG_LEVEL env var)
+    #[arg(long)]
+    pub log_level: Option<String>,
+
+    /// Syntax-highlighting theme for rendered Markdown (overrides LODE_THEME env var)
+    #[arg(long)]
+    pub theme: Option<String>,
+
+    /// Directory of extra `.sublime-syntax` files to load alongside the bundled defaults
+    #[arg(long)]
+    pub syntax_dir: Option<String>,
+
+    /// Directory of extra `.tmTheme` files to load alongside the bundled defaults
+    #[arg(long)]
+    pub theme_dir: Option<String>,
+
+    /// Show a line-number gutter on rendered code blocks
+    #[arg(long)]
+    pub line_numbers: bool,
+
+    /// Also write the completed report in this format: org, md, or txt
+    /// (overrides LODE_EXPORT env var)
+    #[arg(long)]
+    pub export: Option<String>,
+
+    /// Unix domain socket path to publish the live NDJSON event stream on,
+    /// for external dashboards to subscribe mid-run (overrides
+    /// LODE_EVENT_SOCKET env var)
+    #[arg(long)]
+    pub event_socket: Option<String>,
+
+    /// Where `lode.runner` executes: "local" (subprocess, default) or
+    /// "remote" (a worker reachable at --backend-addr) (overrides
+    /// LODE_BACKEND env var)
+    #[arg(long)]
+    pub backend: Option<String>,
+
+    /// `host:port` of the remote worker when --backend=remote, e.g. an
+    /// SSH local-forward's 127.0.0.1:<port> (overrides LODE_BACKEND_ADDR
+    /// env var)
+    #[arg(long)]
+    pub backend_addr: Option<String>,
+
+    /// Run every query in this file (one per line, or a JSON array of
+    /// strings) concurrently and print an aggregate latency/token report
+    /// instead of a single interactive run
+    #[arg(long)]
+    pub batch: Option<String>,
+
+    /// Maximum number of --batch queries to run at once (default 4)
+    #[arg(long)]
+    pub concurrency: Option<u32>,
+
+    /// `host:port` to serve Prometheus-format run telemetry on at `/metrics`
+    /// (overrides LODE_METRICS_ADDR env var); telemetry is still collected
+    /// in-process when unset, just not served
+    #[arg(long)]
+    pub metrics_addr: Option<String>,
+
+    /// Re-render a previously completed run (by its `runs/<id>` directory
+    /// name) from its persisted artifacts instead of starting a new one
+    /// against the backend: the `responses.jsonl` transcript with --json or
+    /// --quiet, the saved prompts, responses and `output.md` in the TUI
+    #[arg(long)]
+    pub replay: Option<String>,
+}
+
+/// Resolves `--metrics-addr`/`LODE_METRICS_ADDR` into the address the
+/// metrics HTTP server should bind, if the user asked for one at all.
+pub fn metrics_addr(cli: &Cli) -> Option<String> {
+    cli.metrics_addr
+        .clone()
+        .or_else(|| env::var("LODE_METRICS_ADDR").ok())
+}
+
+/// Which transport drives `lode.runner`: a local subprocess, or a worker on
+/// another host speaking the same newline-delimited JSON protocol over TCP.
+#[derive(Clone)]
+pub enum BackendTarget {
+    Local,
+    Remote(String),
+}
+
+/// Resolves `--backend`/`LODE_BACKEND` and, for `remote`,
+/// `--backend-addr`/`LODE_BACKEND_ADDR` into a `BackendTarget`. Falls back to
+/// `Local` for anything unrecognized, logging a warning rather than failing.
+pub fn backend_target(cli: &Cli) -> BackendTarget {
+    let requested = cli
+        .backend
+        .clone()
+        .or_else(|| env::var("LODE_BACKEND").ok())
+        .unwrap_or_else(|| "local".to_string());
+
+    match requested.to_ascii_lowercase().as_str() {
+        "local" => BackendTarget::Local,
+        "remote" => {
+            let addr = cli
+                .backend_addr
+                .clone()
+                .or_else(|| env::var("LODE_BACKEND_ADDR").ok())
+                .unwrap_or_else(|| DEFAULT_BACKEND_ADDR.to_string());
+            BackendTarget::Remote(addr)
+        }
+        other => {
+            tracing::warn!(requested = other, "unknown --backend value, defaulting to local");
+            BackendTarget::Local
+        }
+    }
+}
+
+/// Resolved Markdown theme/syntax configuration, threaded from CLI flags
+/// (or their `LODE_*` env var equivalents) into `MarkdownRenderer::with_config`.
+#[derive(Clone)]
+pub struct ThemeConfig {
+    pub theme_name: String,
+    pub extra_syntax_dir: Option<String>,
+    pub extra_theme_dir: Option<String>,
+    pub line_numbers: bool,
+}
+
+pub fn load_theme_config(cli: &Cli) -> ThemeConfig {
+    let theme_name = cli
+        .theme
+        .clone()
+        .or_else(|| env::var("LODE_THEME").ok())
+        .unwrap_or_else(|| crate::tui::DEFAULT_THEME.to_string());
+
+    let extra_syntax_dir = cli
+        .syntax_dir
+        .clone()
+        .or_else(|| env::var("LODE_SYNTAX_DIR").ok());
+
+    let extra_theme_dir = cli
+        .theme_dir
+        .clone()
+        .or_else(|| env::var("LODE_THEME_DIR").ok());
+
+    ThemeConfig {
+        theme_name,
+        extra_syntax_dir,
+        extra_theme_dir,
+        line_numbers: cli.line_numbers,
+    }
+}
+
+/// Resolves `--export`/`LODE_EXPORT` into an `ExportFormat`, logging a
+/// warning and falling back to no export (rather than failing the run) if
+/// the value isn't one of `org`/`md`/`txt`.
+pub fn export_format(cli: &Cli) -> Option<ExportFormat> {
+    let requested = cli.export.clone().or_else(|| env::var("LODE_EXPORT").ok())?;
+    match ExportFormat::parse(&requested) {
+        Some(format) => Some(format),
+        None => {
+            tracing::warn!(requested, "unknown --export format, skipping export");
+            None
+        }
+    }
+}
+
+/// Resolves `--event-socket`/`LODE_EVENT_SOCKET` into a socket path, if the
+/// run should publish its live event stream for external subscribers.
+pub fn event_socket(cli: &Cli) -> Option<String> {
+    cli.event_socket
+        .clone()
+        .or_else(|| env::var("LODE_EVENT_SOCKET").ok())
 }
 
 #[derive(Serialize, Clone)]
@@ -53,6 +223,16 @@ pub struct RequestConfig {
     pub auto_decide: bool,
 }
 
+/// Resolves the runtime log-level filter from `--log-level`, falling back to
+/// `LODE_LOG_LEVEL` and then `"info"`. Accepts anything `tracing_subscriber`'s
+/// `EnvFilter` understands, e.g. `"debug"` or `"lode=trace,warn"`.
+pub fn log_level(cli: &Cli) -> String {
+    cli.log_level
+        .clone()
+        .or_else(|| env::var("LODE_LOG_LEVEL").ok())
+        .unwrap_or_else(|| "info".to_string())
+}
+
 pub fn load_config(cli: &Cli) -> RequestConfig {
     let model = cli
         .model
@@ -0,0 +1,89 @@
+//! Publishes the same NDJSON event records `Output` emits to every client
+//! connected to a Unix domain socket, so an external dashboard can attach
+//! mid-run and watch `prompt`/`response`/`decision` events live without
+//! scraping stdout. Mirrors the language-server pattern of a background
+//! worker pushing structured events to subscribers over a channel.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixListener;
+use tokio::sync::broadcast;
+
+const CHANNEL_CAPACITY: usize = 256;
+
+/// A broadcast channel of serialized event lines, fed by `Output::log_event`
+/// and fanned out to every socket client accepted by `serve`.
+///
+/// Cloning an `EventBus` shares the same channel and handshake cache, so the
+/// handle held by `Output` and the one moved into the accept loop task stay
+/// in sync.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<String>,
+    handshake: Arc<Mutex<Option<String>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _rx) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            tx,
+            handshake: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Publishes one serialized event record to all current subscribers. A
+    /// send with no active receivers isn't an error, it just means nobody's
+    /// watching yet.
+    pub fn publish(&self, line: &str) {
+        let _ = self.tx.send(line.to_string());
+    }
+
+    /// Caches the most recent `start` record so it can be replayed as a
+    /// handshake frame to subscribers who connect mid-run.
+    pub fn set_handshake(&self, line: String) {
+        *self.handshake.lock().unwrap() = Some(line);
+    }
+
+    /// Accepts connections on a Unix domain socket at `path`, writing each
+    /// published event as a newline-delimited JSON line to every connected
+    /// client. Removes any stale socket file left by a previous run before
+    /// binding. Runs until it hits an accept error; callers typically
+    /// `tokio::spawn` this alongside the run.
+    pub async fn serve(self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let mut rx = self.tx.subscribe();
+            let handshake = self.handshake.lock().unwrap().clone();
+
+            tokio::spawn(async move {
+                if let Some(frame) = handshake
+                    && (stream.write_all(frame.as_bytes()).await.is_err()
+                        || stream.write_all(b"\n").await.is_err())
+                {
+                    return;
+                }
+
+                while let Ok(line) = rx.recv().await {
+                    if stream.write_all(line.as_bytes()).await.is_err()
+                        || stream.write_all(b"\n").await.is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
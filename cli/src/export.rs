@@ -0,0 +1,528 @@
+//! Re-serializes a completed run's Markdown report into a user-chosen output
+//! format (`--export org|md|txt`). Parses the report into a small block/inline
+//! node tree, built from the same `pulldown-cmark` event stream and parser
+//! options the CommonMark-walking `MarkdownRenderer` consumes (the renderer
+//! itself lowers events straight into lines without building a tree), and
+//! lowers that tree into the target syntax, rather than string-munging the
+//! Markdown directly.
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Output format for `export`, selected via the `--export` flag.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Org,
+    Markdown,
+    Text,
+}
+
+impl ExportFormat {
+    /// Parses a `--export`/`LODE_EXPORT` value. Accepts a couple of spellings
+    /// per format (`md`/`markdown`, `txt`/`text`) since both are common.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "org" => Some(Self::Org),
+            "md" | "markdown" => Some(Self::Markdown),
+            "txt" | "text" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// File extension to use when writing the exported report to disk.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Org => "org",
+            Self::Markdown => "md",
+            Self::Text => "txt",
+        }
+    }
+}
+
+/// Renders `report` (a Markdown document) in `format`. `Markdown` is the
+/// identity case — the report is already Markdown — so only `Org` and `Text`
+/// actually parse and re-lower the document.
+pub fn export(report: &str, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => report.to_string(),
+        ExportFormat::Org => render_org(&parse_blocks(report)),
+        ExportFormat::Text => render_text(&parse_blocks(report)),
+    }
+}
+
+#[derive(Clone)]
+enum Inline {
+    Text(String),
+    Code(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Strikethrough(Vec<Inline>),
+    Link { text: Vec<Inline>, url: String },
+    SoftBreak,
+    HardBreak,
+}
+
+#[derive(Clone)]
+struct ListItem {
+    checked: Option<bool>,
+    blocks: Vec<Block>,
+}
+
+#[derive(Clone)]
+enum Block {
+    Heading { level: HeadingLevel, inline: Vec<Inline> },
+    Paragraph(Vec<Inline>),
+    CodeBlock { lang: Option<String>, code: String },
+    List { ordered: bool, items: Vec<ListItem> },
+    BlockQuote(Vec<Block>),
+    Table { alignments: Vec<Alignment>, rows: Vec<Vec<String>> },
+    FootnoteDefinition { label: String, blocks: Vec<Block> },
+    Rule,
+}
+
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+}
+
+/// Walks the CommonMark event stream into a tree of `Block`/`Inline` nodes,
+/// tracking block nesting (list/blockquote) as an explicit stack of in-
+/// progress node vectors rather than re-deriving it from indentation.
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let parser = Parser::new_ext(markdown, parser_options());
+    let mut stack: Vec<Vec<Block>> = vec![Vec::new()];
+    let mut list_stack: Vec<bool> = Vec::new(); // ordered?
+    let mut list_item_stack: Vec<Vec<ListItem>> = Vec::new();
+    let mut item_stack: Vec<Option<bool>> = Vec::new(); // pending checkbox
+    let mut footnote_label_stack: Vec<String> = Vec::new();
+    let mut inline_stack: Vec<Vec<Inline>> = vec![Vec::new()];
+    let mut emphasis_stack: Vec<fn(Vec<Inline>) -> Inline> = Vec::new();
+    let mut link_url: Vec<String> = Vec::new();
+    let mut heading_level: Option<HeadingLevel> = None;
+    let mut code_lang: Option<String> = None;
+    let mut code_buffer = String::new();
+    let mut in_code_block = false;
+    let mut table_alignments = Vec::new();
+    let mut table_rows: Vec<Vec<String>> = Vec::new();
+    let mut table_current_row: Vec<String> = Vec::new();
+    let mut in_table_cell = false;
+    let mut table_current_cell = String::new();
+
+    for event in parser {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => heading_level = Some(level),
+            Event::End(TagEnd::Heading(_)) => {
+                let inline = std::mem::replace(inline_stack.last_mut().unwrap(), Vec::new());
+                stack.last_mut().unwrap().push(Block::Heading {
+                    level: heading_level.take().unwrap_or(HeadingLevel::H1),
+                    inline,
+                });
+            }
+            Event::Start(Tag::Paragraph) => {}
+            Event::End(TagEnd::Paragraph) => {
+                let inline = std::mem::take(inline_stack.last_mut().unwrap());
+                if in_table_cell {
+                    table_current_cell.push_str(&inline_text(&inline));
+                } else if !inline.is_empty() {
+                    stack.last_mut().unwrap().push(Block::Paragraph(inline));
+                }
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                in_code_block = true;
+                code_lang = match kind {
+                    CodeBlockKind::Fenced(lang) if !lang.is_empty() => Some(lang.to_string()),
+                    _ => None,
+                };
+                code_buffer.clear();
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                stack.last_mut().unwrap().push(Block::CodeBlock {
+                    lang: code_lang.take(),
+                    code: std::mem::take(&mut code_buffer),
+                });
+            }
+            Event::Start(Tag::List(start)) => {
+                list_stack.push(start.is_some());
+                list_item_stack.push(Vec::new());
+            }
+            Event::End(TagEnd::List(_)) => {
+                let ordered = list_stack.pop().unwrap_or(false);
+                let items = list_item_stack.pop().unwrap_or_default();
+                stack.last_mut().unwrap().push(Block::List { ordered, items });
+            }
+            Event::Start(Tag::Item) => {
+                item_stack.push(None);
+                stack.push(Vec::new());
+            }
+            Event::End(TagEnd::Item) => {
+                let checked = item_stack.pop().flatten();
+                // A tight list (no blank lines between items) never emits a
+                // `Paragraph`, so its text only ever lands in `inline_stack`.
+                // Wrap it into the item's own paragraph before taking its
+                // blocks; for a loose list `Paragraph` has already consumed
+                // it and this is a no-op.
+                let inline = std::mem::take(inline_stack.last_mut().unwrap());
+                if !inline.is_empty() {
+                    stack.last_mut().unwrap().push(Block::Paragraph(inline));
+                }
+                let blocks = std::mem::take(stack.last_mut().unwrap());
+                stack.pop();
+                list_item_stack
+                    .last_mut()
+                    .unwrap()
+                    .push(ListItem { checked, blocks });
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(slot) = item_stack.last_mut() {
+                    *slot = Some(checked);
+                }
+            }
+            Event::Start(Tag::BlockQuote(_)) => stack.push(Vec::new()),
+            Event::End(TagEnd::BlockQuote) => {
+                let blocks = std::mem::take(stack.last_mut().unwrap());
+                stack.pop();
+                stack.last_mut().unwrap().push(Block::BlockQuote(blocks));
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                footnote_label_stack.push(label.to_string());
+                stack.push(Vec::new());
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                let blocks = std::mem::take(stack.last_mut().unwrap());
+                stack.pop();
+                let label = footnote_label_stack.pop().unwrap_or_default();
+                stack.last_mut().unwrap().push(Block::FootnoteDefinition { label, blocks });
+            }
+            Event::FootnoteReference(label) => {
+                inline_stack.last_mut().unwrap().push(Inline::Text(format!("[^{}]", label)));
+            }
+            Event::Rule => stack.last_mut().unwrap().push(Block::Rule),
+            Event::Start(Tag::Table(alignments)) => {
+                table_alignments = alignments;
+                table_rows.clear();
+            }
+            Event::End(TagEnd::Table) => {
+                stack.last_mut().unwrap().push(Block::Table {
+                    alignments: std::mem::take(&mut table_alignments),
+                    rows: std::mem::take(&mut table_rows),
+                });
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                table_current_row.clear();
+            }
+            Event::End(TagEnd::TableHead) | Event::End(TagEnd::TableRow) => {
+                table_rows.push(std::mem::take(&mut table_current_row));
+            }
+            Event::Start(Tag::TableCell) => {
+                in_table_cell = true;
+                table_current_cell.clear();
+            }
+            Event::End(TagEnd::TableCell) => {
+                in_table_cell = false;
+                table_current_row.push(std::mem::take(&mut table_current_cell));
+            }
+            Event::Start(Tag::Emphasis) => {
+                emphasis_stack.push(Inline::Emphasis);
+                inline_stack.push(Vec::new());
+            }
+            Event::Start(Tag::Strong) => {
+                emphasis_stack.push(Inline::Strong);
+                inline_stack.push(Vec::new());
+            }
+            Event::Start(Tag::Strikethrough) => {
+                emphasis_stack.push(Inline::Strikethrough);
+                inline_stack.push(Vec::new());
+            }
+            Event::End(TagEnd::Emphasis) | Event::End(TagEnd::Strong) | Event::End(TagEnd::Strikethrough) => {
+                let inline = inline_stack.pop().unwrap_or_default();
+                let wrap = emphasis_stack.pop().unwrap_or(Inline::Emphasis as fn(Vec<Inline>) -> Inline);
+                inline_stack.last_mut().unwrap().push(wrap(inline));
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                link_url.push(dest_url.to_string());
+                inline_stack.push(Vec::new());
+            }
+            Event::End(TagEnd::Link) => {
+                let text = inline_stack.pop().unwrap_or_default();
+                let url = link_url.pop().unwrap_or_default();
+                inline_stack.last_mut().unwrap().push(Inline::Link { text, url });
+            }
+            Event::Text(text) => {
+                if in_code_block {
+                    code_buffer.push_str(&text);
+                } else if in_table_cell {
+                    table_current_cell.push_str(&text);
+                } else {
+                    inline_stack.last_mut().unwrap().push(Inline::Text(text.into_string()));
+                }
+            }
+            Event::Code(text) => {
+                if in_table_cell {
+                    table_current_cell.push_str(&text);
+                } else {
+                    inline_stack.last_mut().unwrap().push(Inline::Code(text.into_string()));
+                }
+            }
+            Event::SoftBreak => {
+                if in_table_cell {
+                    table_current_cell.push(' ');
+                } else {
+                    inline_stack.last_mut().unwrap().push(Inline::SoftBreak);
+                }
+            }
+            Event::HardBreak => inline_stack.last_mut().unwrap().push(Inline::HardBreak),
+            _ => {}
+        }
+    }
+
+    stack.into_iter().next().unwrap_or_default()
+}
+
+fn inline_text(inline: &[Inline]) -> String {
+    let mut out = String::new();
+    for part in inline {
+        match part {
+            Inline::Text(t) | Inline::Code(t) => out.push_str(t),
+            Inline::Emphasis(i) | Inline::Strong(i) | Inline::Strikethrough(i) => {
+                out.push_str(&inline_text(i))
+            }
+            Inline::Link { text, .. } => out.push_str(&inline_text(text)),
+            Inline::SoftBreak => out.push(' '),
+            Inline::HardBreak => out.push('\n'),
+        }
+    }
+    out
+}
+
+fn org_inline(inline: &[Inline]) -> String {
+    let mut out = String::new();
+    for part in inline {
+        match part {
+            Inline::Text(t) => out.push_str(t),
+            Inline::Code(t) => out.push_str(&format!("={}=", t)),
+            Inline::Emphasis(i) => out.push_str(&format!("/{}/", org_inline(i))),
+            Inline::Strong(i) => out.push_str(&format!("*{}*", org_inline(i))),
+            Inline::Strikethrough(i) => out.push_str(&format!("+{}+", org_inline(i))),
+            Inline::Link { text, url } => {
+                out.push_str(&format!("[[{}][{}]]", url, org_inline(text)))
+            }
+            Inline::SoftBreak => out.push(' '),
+            Inline::HardBreak => out.push('\n'),
+        }
+    }
+    out
+}
+
+fn render_org(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    render_org_blocks(blocks, &mut out);
+    out
+}
+
+fn render_org_blocks(blocks: &[Block], out: &mut String) {
+    for block in blocks {
+        match block {
+            Block::Heading { level, inline } => {
+                let stars = "*".repeat(heading_depth(*level));
+                out.push_str(&format!("{} {}\n\n", stars, org_inline(inline)));
+            }
+            Block::Paragraph(inline) => {
+                out.push_str(&org_inline(inline));
+                out.push_str("\n\n");
+            }
+            Block::CodeBlock { lang, code } => {
+                let header = match lang {
+                    Some(lang) => format!("#+BEGIN_SRC {}", lang),
+                    None => "#+BEGIN_SRC".to_string(),
+                };
+                out.push_str(&header);
+                out.push('\n');
+                out.push_str(code.trim_end_matches('\n'));
+                out.push_str("\n#+END_SRC\n\n");
+            }
+            Block::List { ordered, items } => {
+                for (index, item) in items.iter().enumerate() {
+                    let marker = if *ordered {
+                        format!("{}. ", index + 1)
+                    } else {
+                        "- ".to_string()
+                    };
+                    let checkbox = match item.checked {
+                        Some(true) => "[X] ",
+                        Some(false) => "[ ] ",
+                        None => "",
+                    };
+                    let mut body = String::new();
+                    render_org_blocks(&item.blocks, &mut body);
+                    let body = body.trim_end_matches('\n');
+                    out.push_str(&marker);
+                    out.push_str(checkbox);
+                    out.push_str(&indent_continuation(body, marker.len() + checkbox.len()));
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            Block::BlockQuote(inner) => {
+                out.push_str("#+BEGIN_QUOTE\n");
+                render_org_blocks(inner, out);
+                out.push_str("#+END_QUOTE\n\n");
+            }
+            Block::Table { rows, .. } => {
+                render_org_table(rows, out);
+                out.push('\n');
+            }
+            Block::FootnoteDefinition { label, blocks } => {
+                let marker = format!("[fn:{}] ", label);
+                let mut body = String::new();
+                render_org_blocks(blocks, &mut body);
+                out.push_str(&marker);
+                out.push_str(body.trim_end_matches('\n'));
+                out.push_str("\n\n");
+            }
+            Block::Rule => out.push_str("-----\n\n"),
+        }
+    }
+}
+
+/// Indents every line after the first by `width` spaces, so a multi-line list
+/// item body lines up under its marker instead of back at column zero.
+fn indent_continuation(body: &str, width: usize) -> String {
+    let indent = " ".repeat(width);
+    body.lines()
+        .enumerate()
+        .map(|(i, line)| if i == 0 { line.to_string() } else { format!("{}{}", indent, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_org_table(rows: &[Vec<String>], out: &mut String) {
+    for (row_index, row) in rows.iter().enumerate() {
+        out.push('|');
+        for cell in row {
+            out.push_str(&format!(" {} |", cell));
+        }
+        out.push('\n');
+        if row_index == 0 {
+            out.push_str("|-\n");
+        }
+    }
+}
+
+fn render_text(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    render_text_blocks(blocks, &mut out);
+    out
+}
+
+fn render_text_blocks(blocks: &[Block], out: &mut String) {
+    for block in blocks {
+        match block {
+            Block::Heading { inline, .. } => {
+                out.push_str(&inline_text(inline));
+                out.push_str("\n\n");
+            }
+            Block::Paragraph(inline) => {
+                out.push_str(&inline_text(inline));
+                out.push_str("\n\n");
+            }
+            Block::CodeBlock { code, .. } => {
+                for line in code.trim_end_matches('\n').lines() {
+                    out.push_str("    ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            Block::List { ordered, items } => {
+                for (index, item) in items.iter().enumerate() {
+                    let marker = if *ordered {
+                        format!("{}. ", index + 1)
+                    } else {
+                        "- ".to_string()
+                    };
+                    let checkbox = match item.checked {
+                        Some(true) => "[x] ",
+                        Some(false) => "[ ] ",
+                        None => "",
+                    };
+                    let mut body = String::new();
+                    render_text_blocks(&item.blocks, &mut body);
+                    let body = body.trim_end_matches('\n');
+                    out.push_str(&marker);
+                    out.push_str(checkbox);
+                    out.push_str(&indent_continuation(body, marker.len() + checkbox.len()));
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            Block::BlockQuote(inner) => {
+                let mut body = String::new();
+                render_text_blocks(inner, &mut body);
+                for line in body.trim_end_matches('\n').lines() {
+                    out.push_str("> ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            Block::Table { rows, .. } => {
+                for row in rows {
+                    out.push_str(&row.join(" | "));
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            Block::FootnoteDefinition { label, blocks } => {
+                let marker = format!("[^{}]: ", label);
+                let mut body = String::new();
+                render_text_blocks(blocks, &mut body);
+                out.push_str(&marker);
+                out.push_str(body.trim_end_matches('\n'));
+                out.push_str("\n\n");
+            }
+            Block::Rule => out.push_str("-----\n\n"),
+        }
+    }
+}
+
+fn heading_depth(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tight list (no blank lines between items) never emits a `Paragraph`
+    /// event, so each item's text must still end up in the item's body
+    /// instead of being dropped.
+    #[test]
+    fn tight_list_items_export_with_body() {
+        let report = "- a\n- b\n- c\n";
+
+        let org = export(report, ExportFormat::Org);
+        assert_eq!(org, "- a\n- b\n- c\n\n");
+
+        let text = export(report, ExportFormat::Text);
+        assert_eq!(text, "- a\n- b\n- c\n\n");
+    }
+
+    /// A loose list (blank line between items) does go through `Paragraph`
+    /// and must keep rendering the same as before this fix.
+    #[test]
+    fn loose_list_items_export_with_body() {
+        let report = "- a\n\n- b\n";
+
+        let org = export(report, ExportFormat::Org);
+        assert_eq!(org, "- a\n- b\n\n");
+    }
+}
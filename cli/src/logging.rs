@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::EnvFilter;
+
+pub const LOGS_DIR: &str = "logs";
+
+/// Installs a `tracing` subscriber that writes structured logs to a rolling
+/// daily file under `logs/`, never to stdout — the TUI owns the alternate
+/// screen, and any stray write there would corrupt the frame.
+///
+/// Returns a guard that must be kept alive for the life of the process
+/// (dropping it stops the background flush thread) and the directory logs
+/// are written to, so callers can surface the path in the status bar.
+pub fn init(log_level: &str) -> Result<(WorkerGuard, PathBuf), Box<dyn std::error::Error>> {
+    let logs_dir = PathBuf::from(LOGS_DIR);
+    std::fs::create_dir_all(&logs_dir)?;
+
+    let file_appender = Builder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("lode")
+        .filename_suffix("log")
+        .max_log_files(14)
+        .build(&logs_dir)?;
+    let (writer, guard) = tracing_appender::non_blocking(file_appender);
+
+    let filter = EnvFilter::try_new(log_level).unwrap_or_else(|_| EnvFilter::new("info"));
+
+    tracing_subscriber::fmt()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_env_filter(filter)
+        .init();
+
+    Ok((guard, logs_dir))
+}
@@ -1,24 +1,35 @@
+mod backend;
+mod bench;
 mod cli;
+mod event_bus;
+mod export;
+mod logging;
+mod metrics;
 mod output;
+mod pricing;
 mod protocol;
+mod replay;
 mod run;
 mod tui;
 
-use std::process::Stdio;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use clap::Parser;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::Command;
 use tokio::sync::{Mutex, mpsc, oneshot};
 use uuid::Uuid;
 
+use backend::{AnyBackend, Backend, BackendReader, BackendWriter};
 use cli::{Cli, RequestConfig, load_config};
+use event_bus::EventBus;
+use export::ExportFormat;
+use metrics::Metrics;
 use output::Output;
 use protocol::{ClarifyingAnswers, InterruptCommand, Request, Response};
 use run::{
-    RunContext, setup_run_directory, write_metadata, write_output, write_prompt,
-    write_raw_response, write_request,
+    RunContext, setup_run_directory, write_export, write_metadata, write_output, write_prompt,
+    write_raw_response, write_request, write_tool_call, write_transcript,
 };
 use tui::{App, AppEvent, Tui};
 
@@ -27,18 +38,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let _ = dotenvy::dotenv();
 
     let cli = Cli::parse();
+    let (_log_guard, logs_dir) = logging::init(&cli::log_level(&cli))?;
 
-    if cli.json || cli.quiet || !cli.query.is_empty() {
-        run_single_query(&cli).await
+    let metrics = Arc::new(Metrics::new());
+    if let Some(addr) = cli::metrics_addr(&cli) {
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(metrics, &addr).await {
+                tracing::warn!(error = %e, "metrics server stopped");
+            }
+        });
+    }
+
+    if let Some(run_id) = cli.replay.clone() {
+        replay::run_replay(&cli, &run_id, &logs_dir).await
+    } else if let Some(batch_path) = cli.batch.clone() {
+        bench::run_batch(&cli, &batch_path).await
+    } else if cli.json || cli.quiet || !cli.query.is_empty() {
+        run_single_query(&cli, metrics).await
     } else {
-        run_tui(&cli).await
+        run_tui(&cli, &logs_dir, metrics).await
     }
 }
 
-async fn run_tui(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+async fn run_tui(
+    cli: &Cli,
+    logs_dir: &Path,
+    metrics: Arc<Metrics>,
+) -> Result<(), Box<dyn std::error::Error>> {
     let config = load_config(cli);
-    let mut app = App::new();
+    let theme_config = cli::load_theme_config(cli);
+    let export_format = cli::export_format(cli);
+    let mut app = App::new(!cli.no_auto, config.model.clone(), theme_config);
+    app.log_path = Some(logs_dir.display().to_string());
     let mut tui_instance = Tui::new()?;
+    let backend_target = cli::backend_target(cli);
 
     let event_tx = app.event_sender();
     let answer_slot: Arc<Mutex<Option<oneshot::Sender<Vec<String>>>>> = Arc::new(Mutex::new(None));
@@ -48,7 +82,9 @@ async fn run_tui(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
     let answer_slot_submit = answer_slot.clone();
     let answer_slot_answers = answer_slot.clone();
     let interrupt_tx_submit = interrupt_tx.clone();
+    let interrupt_tx_answers = interrupt_tx.clone();
     let interrupt_tx_tui = interrupt_tx.clone();
+    let event_tx_save = event_tx.clone();
 
     tui_instance
         .run(
@@ -59,6 +95,8 @@ async fn run_tui(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
                 let tx = event_tx.clone();
                 let answer_slot = answer_slot_submit.clone();
                 let interrupt_slot = interrupt_tx_submit.clone();
+                let backend_target = backend_target.clone();
+                let metrics = metrics.clone();
 
                 tokio::spawn(async move {
                     let (int_tx, int_rx) = mpsc::unbounded_channel();
@@ -67,10 +105,23 @@ async fn run_tui(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
                         *guard = Some(int_tx);
                     }
 
-                    if let Err(e) =
-                        run_research_query(&query, &config, tx.clone(), answer_slot, Some(int_rx))
+                    let result = match AnyBackend::connect(&backend_target).await {
+                        Ok(backend) => {
+                            run_research_query(
+                                &query,
+                                &config,
+                                export_format,
+                                tx.clone(),
+                                answer_slot,
+                                Some(int_rx),
+                                backend,
+                                metrics,
+                            )
                             .await
-                    {
+                        }
+                        Err(e) => Err(format!("failed to reach backend: {}", e)),
+                    };
+                    if let Err(e) = result {
                         let _ = tx.send(AppEvent::Error(e));
                     }
 
@@ -81,12 +132,24 @@ async fn run_tui(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
                     }
                 });
             },
-            move |answers| {
+            move |answers, is_final| {
                 let slot = answer_slot_answers.clone();
+                let interrupt_slot = interrupt_tx_answers.clone();
                 tokio::spawn(async move {
-                    let mut guard = slot.lock().await;
-                    if let Some(tx) = guard.take() {
-                        let _ = tx.send(answers);
+                    if is_final {
+                        let mut guard = slot.lock().await;
+                        if let Some(tx) = guard.take() {
+                            let _ = tx.send(answers);
+                        }
+                    } else {
+                        // Cancelled at the confirm prompt: drop the answers
+                        // instead of forwarding them, and interrupt the
+                        // already-running query rather than let it proceed.
+                        slot.lock().await.take();
+                        let guard = interrupt_slot.lock().await;
+                        if let Some(ref tx) = *guard {
+                            let _ = tx.send(InterruptCommand::Stop);
+                        }
                     }
                 });
             },
@@ -99,36 +162,51 @@ async fn run_tui(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
                     }
                 });
             },
+            move |markdown, run_id| {
+                let tx = event_tx_save.clone();
+                tokio::spawn(async move {
+                    match write_transcript(&markdown, run_id.as_deref()).await {
+                        Ok(path) => {
+                            let _ = tx.send(AppEvent::Saved {
+                                path: path.display().to_string(),
+                            });
+                        }
+                        Err(e) => {
+                            let _ = tx.send(AppEvent::Error(format!("save failed: {}", e)));
+                        }
+                    }
+                });
+            },
         )
         .await?;
 
     Ok(())
 }
 
-async fn send_interrupt(
-    stdin: &Arc<Mutex<tokio::process::ChildStdin>>,
+async fn send_interrupt<W: BackendWriter>(
+    writer: &Arc<Mutex<W>>,
     command: InterruptCommand,
 ) -> Result<(), String> {
     use protocol::Interrupt;
     let interrupt = Interrupt::new(command);
     let json = interrupt.to_json();
-    let mut guard = stdin.lock().await;
-    guard
-        .write_all(json.as_bytes())
-        .await
-        .map_err(|e| e.to_string())?;
-    guard.write_all(b"\n").await.map_err(|e| e.to_string())?;
-    Ok(())
+    let mut guard = writer.lock().await;
+    guard.send_line(&json).await.map_err(|e| e.to_string())
 }
 
-async fn run_research_query(
+#[tracing::instrument(skip(query, config, export_format, event_tx, answer_slot, interrupt_rx, backend, metrics))]
+async fn run_research_query<B: Backend>(
     query: &str,
     config: &RequestConfig,
+    export_format: Option<ExportFormat>,
     event_tx: mpsc::UnboundedSender<AppEvent>,
     answer_slot: Arc<Mutex<Option<oneshot::Sender<Vec<String>>>>>,
     interrupt_rx: Option<mpsc::UnboundedReceiver<InterruptCommand>>,
+    backend: B,
+    metrics: Arc<Metrics>,
 ) -> Result<(), String> {
     let run_id = Uuid::new_v4().to_string();
+    tracing::info!(%run_id, "starting research run");
     let request = Request {
         version: "v1",
         run_id: run_id.clone(),
@@ -144,45 +222,36 @@ async fn run_research_query(
         .map_err(|e| e.to_string())?;
 
     let mut ctx = RunContext::new(run_dir.clone());
+    let mut remaining_searches = None;
+    let mut remaining_iterations = None;
+    let mut clarifying_rounds = 0u32;
 
-    let mut child = Command::new("uv")
-        .args(["run", "python", "-m", "lode.runner"])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .map_err(|e| e.to_string())?;
-
-    let stdin = Arc::new(Mutex::new(child.stdin.take().expect("Failed to open stdin")));
+    let (writer, mut reader) = backend.split();
+    let writer = Arc::new(Mutex::new(writer));
     let request_json = serde_json::to_string(&request).map_err(|e| e.to_string())?;
     {
-        let mut guard = stdin.lock().await;
-        guard
-            .write_all(request_json.as_bytes())
-            .await
-            .map_err(|e| e.to_string())?;
-        guard.write_all(b"\n").await.map_err(|e| e.to_string())?;
+        let mut guard = writer.lock().await;
+        guard.send_line(&request_json).await.map_err(|e| e.to_string())?;
     }
 
     // Spawn interrupt handler if receiver provided
-    let stdin_for_interrupt = stdin.clone();
+    let writer_for_interrupt = writer.clone();
     let interrupt_handle = interrupt_rx.map(|mut rx| {
         tokio::spawn(async move {
             while let Some(cmd) = rx.recv().await {
-                let _ = send_interrupt(&stdin_for_interrupt, cmd).await;
+                let _ = send_interrupt(&writer_for_interrupt, cmd).await;
             }
         })
     });
 
-    let stdout = child.stdout.take().expect("Failed to open stdout");
-    let reader = BufReader::new(stdout);
-    let mut lines = reader.lines();
-
     let mut success = false;
     let mut answers_sent = false;
 
-    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+    while let Some(line) = reader.next_line().await.map_err(|e| e.to_string())? {
         if let Ok(response) = serde_json::from_str::<Response>(&line) {
+            if let Err(e) = run::append_response(&run_dir, &line).await {
+                tracing::warn!(error = %e, "failed to append response log");
+            }
             match &response {
                 Response::Trace {
                     trace_id,
@@ -192,6 +261,7 @@ async fn run_research_query(
                     ctx.trace_url = Some(trace_url.clone());
                 }
                 Response::ClarifyingQuestions { .. } if !answers_sent => {
+                    clarifying_rounds += 1;
                     let _ = event_tx.send(AppEvent::BackendResponse(response.clone()));
 
                     let (tx, rx) = oneshot::channel();
@@ -205,12 +275,11 @@ async fn run_research_query(
                             let answers_msg = ClarifyingAnswers { answers };
                             let answers_json =
                                 serde_json::to_string(&answers_msg).map_err(|e| e.to_string())?;
-                            let mut guard = stdin.lock().await;
+                            let mut guard = writer.lock().await;
                             guard
-                                .write_all(answers_json.as_bytes())
+                                .send_line(&answers_json)
                                 .await
                                 .map_err(|e| e.to_string())?;
-                            guard.write_all(b"\n").await.map_err(|e| e.to_string())?;
                             answers_sent = true;
                         }
                         Err(_) => {
@@ -224,7 +293,9 @@ async fn run_research_query(
                     sequence,
                     content,
                 } => {
-                    let _ = write_prompt(&ctx, agent, *sequence, content).await;
+                    if let Err(e) = write_prompt(&ctx, agent, *sequence, content).await {
+                        tracing::warn!(agent, sequence, error = %e, "failed to write prompt");
+                    }
                 }
                 Response::AgentOutput {
                     agent,
@@ -232,17 +303,37 @@ async fn run_research_query(
                     content,
                     token_usage,
                 } => {
-                    let _ = write_raw_response(
+                    if let Err(e) = write_raw_response(
                         &ctx,
                         agent,
                         *sequence,
                         content,
                         token_usage.as_ref(),
                     )
-                    .await;
+                    .await
+                    {
+                        tracing::warn!(agent, sequence, error = %e, "failed to write agent response");
+                    }
                 }
-                Response::Decision { .. } => {
+                Response::ToolCall {
+                    agent,
+                    sequence,
+                    name,
+                    args,
+                    status,
+                } => {
+                    if let Err(e) = write_tool_call(&ctx, agent, *sequence, name, args, status).await {
+                        tracing::warn!(agent, sequence, name, error = %e, "failed to write tool call");
+                    }
+                }
+                Response::Decision {
+                    remaining_searches: s,
+                    remaining_iterations: i,
+                    ..
+                } => {
                     // Decision events are displayed via TUI status updates
+                    remaining_searches = Some(*s);
+                    remaining_iterations = Some(*i);
                 }
                 Response::Report {
                     markdown_report, ..
@@ -260,6 +351,29 @@ async fn run_research_query(
                 Response::Done { success: s } => {
                     success = *s;
                 }
+                Response::StreamBegin { agent, sequence } => {
+                    let _ = event_tx.send(AppEvent::StreamBegin {
+                        message_id: format!("{}-{}", agent, sequence),
+                    });
+                    continue;
+                }
+                Response::StreamDelta {
+                    agent,
+                    sequence,
+                    text,
+                } => {
+                    let _ = event_tx.send(AppEvent::StreamDelta {
+                        message_id: format!("{}-{}", agent, sequence),
+                        text: text.clone(),
+                    });
+                    continue;
+                }
+                Response::StreamEnd { agent, sequence } => {
+                    let _ = event_tx.send(AppEvent::StreamEnd {
+                        message_id: format!("{}-{}", agent, sequence),
+                    });
+                    continue;
+                }
                 _ => {}
             }
 
@@ -272,32 +386,51 @@ async fn run_research_query(
         handle.abort();
     }
 
-    drop(stdin);
-    let status = child.wait().await.map_err(|e| e.to_string())?;
+    drop(writer);
+    let transport_ok = reader.finished_successfully().await.map_err(|e| e.to_string())?;
 
     if let Some(ref markdown) = ctx.markdown_report {
         let _ = write_output(&run_dir, markdown).await;
+        if let Some(format) = export_format {
+            let _ = write_export(&run_dir, markdown, format).await;
+        }
     }
 
     let metadata = ctx.to_metadata(run_id.clone());
     let _ = write_metadata(&run_dir, &metadata).await;
 
+    let final_success = success && transport_ok;
+    tracing::info!(%run_id, success = final_success, "research run finished");
+
+    metrics.record_run(
+        ctx.model.as_deref(),
+        final_success,
+        Duration::from_millis(ctx.elapsed_ms()),
+        ctx.total_tokens,
+        remaining_searches.map(|r| config.max_searches.saturating_sub(r)),
+        remaining_iterations.map(|r| config.max_iterations.saturating_sub(r)),
+        clarifying_rounds,
+    );
+
     let _ = event_tx.send(AppEvent::RunComplete {
-        success: success && status.success(),
+        success: final_success,
         run_id,
     });
 
     Ok(())
 }
 
-async fn run_single_query(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+async fn run_single_query(
+    cli: &Cli,
+    metrics: Arc<Metrics>,
+) -> Result<(), Box<dyn std::error::Error>> {
     use std::io::{BufRead, Write};
 
     let output = Output::new(cli.json, cli.quiet);
     let query = cli.query.join(" ");
 
     if query.is_empty() {
-        output.error(Some("MISSING_QUERY"), "query is required");
+        output.error(Some("MISSING_QUERY"), "query is required", None);
         eprintln!("Usage: lode <query>");
         std::process::exit(1);
     }
@@ -314,137 +447,183 @@ async fn run_single_query(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
     let run_dir = setup_run_directory(&run_id).await?;
     write_request(&run_dir, &request).await?;
 
+    let output = output.with_event_log(run_dir.join("events.ndjson"))?;
+    let output = if let Some(socket_path) = cli::event_socket(cli) {
+        let bus = EventBus::new();
+        let serve_bus = bus.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_bus.serve(&socket_path).await {
+                tracing::warn!(error = %e, "event bus socket server stopped");
+            }
+        });
+        output.with_event_bus(bus)
+    } else {
+        output
+    };
+
     output.start(&run_id, &run_dir, &config);
 
     let mut ctx = RunContext::new(run_dir.clone());
 
-    let mut child = Command::new("uv")
-        .args(["run", "python", "-m", "lode.runner"])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::inherit())
-        .spawn()?;
-
-    let mut stdin = child.stdin.take().expect("Failed to open stdin");
+    let backend_target = cli::backend_target(cli);
+    let backend = AnyBackend::connect(&backend_target).await?;
+    let (mut writer, mut reader) = backend.split();
     let request_json = serde_json::to_string(&request)?;
-    stdin.write_all(request_json.as_bytes()).await?;
-    stdin.write_all(b"\n").await?;
-
-    let stdout = child.stdout.take().expect("Failed to open stdout");
-    let reader = BufReader::new(stdout);
-    let mut lines = reader.lines();
+    writer.send_line(&request_json).await?;
 
     let mut success = false;
     let mut answers_sent = false;
+    let mut remaining_searches = None;
+    let mut remaining_iterations = None;
+    let mut clarifying_rounds = 0u32;
 
-    while let Some(line) = lines.next_line().await? {
+    while let Some(line) = reader.next_line().await? {
         match serde_json::from_str::<Response>(&line) {
-            Ok(response) => match response {
-                Response::Status { message } => {
-                    output.status(&message);
+            Ok(response) => {
+                if let Err(e) = run::append_response(&run_dir, &line).await {
+                    output.warning(&format!("failed to append response log: {}", e));
                 }
-                Response::Trace {
-                    trace_id,
-                    trace_url,
-                } => {
-                    output.trace(&trace_id, &trace_url);
-                    ctx.trace_id = Some(trace_id);
-                    ctx.trace_url = Some(trace_url);
-                }
-                Response::ClarifyingQuestions { questions } if !answers_sent => {
-                    eprintln!("\nPlease answer these clarifying questions:");
-                    let mut answers = Vec::new();
-                    let term_stdin = std::io::stdin();
-
-                    for (i, q) in questions.iter().enumerate() {
-                        eprintln!("\n{}. [{}] {}", i + 1, q.label, q.question);
-                        eprint!("> ");
-                        std::io::stderr().flush().ok();
-
-                        let mut answer = String::new();
-                        term_stdin.lock().read_line(&mut answer).ok();
-                        answers.push(answer.trim().to_string());
+                match response {
+                    Response::Status { message } => {
+                        output.status(&message, None);
+                    }
+                    Response::Trace {
+                        trace_id,
+                        trace_url,
+                    } => {
+                        output.trace(&trace_id, &trace_url);
+                        ctx.trace_id = Some(trace_id);
+                        ctx.trace_url = Some(trace_url);
                     }
+                    Response::ClarifyingQuestions { questions } if !answers_sent => {
+                        clarifying_rounds += 1;
+                        eprintln!("\nPlease answer these clarifying questions:");
+                        let mut answers = Vec::new();
+                        let term_stdin = std::io::stdin();
+
+                        for (i, q) in questions.iter().enumerate() {
+                            eprintln!("\n{}. [{}] {}", i + 1, q.label, q.question);
+                            eprint!("> ");
+                            std::io::stderr().flush().ok();
+
+                            let mut answer = String::new();
+                            term_stdin.lock().read_line(&mut answer).ok();
+                            answers.push(answer.trim().to_string());
+                        }
 
-                    eprintln!();
-                    let answers_msg = ClarifyingAnswers { answers };
-                    let answers_json = serde_json::to_string(&answers_msg)?;
-                    stdin.write_all(answers_json.as_bytes()).await?;
-                    stdin.write_all(b"\n").await?;
-                    answers_sent = true;
-                }
-                Response::ClarifyingQuestions { .. } => {
-                    // Already sent answers, ignore
-                }
-                Response::Prompt {
-                    agent,
-                    sequence,
-                    content,
-                } => {
-                    output.prompt(&agent, sequence);
-                    if let Err(e) = write_prompt(&ctx, &agent, sequence, &content).await {
-                        output.warning(&format!("failed to write prompt: {}", e));
+                        eprintln!();
+                        let answers_msg = ClarifyingAnswers { answers };
+                        let answers_json = serde_json::to_string(&answers_msg)?;
+                        writer.send_line(&answers_json).await?;
+                        answers_sent = true;
                     }
-                }
-                Response::AgentOutput {
-                    agent,
-                    sequence,
-                    content,
-                    token_usage,
-                } => {
-                    output.response(&agent, sequence);
-                    if let Err(e) =
-                        write_raw_response(&ctx, &agent, sequence, &content, token_usage.as_ref())
-                            .await
-                    {
-                        output.warning(&format!("failed to write response: {}", e));
+                    Response::ClarifyingQuestions { .. } => {
+                        // Already sent answers, ignore
+                    }
+                    Response::Prompt {
+                        agent,
+                        sequence,
+                        content,
+                    } => {
+                        output.prompt(&agent, sequence, Some(&agent));
+                        if let Err(e) = write_prompt(&ctx, &agent, sequence, &content).await {
+                            output.warning(&format!("failed to write prompt: {}", e));
+                        }
+                    }
+                    Response::AgentOutput {
+                        agent,
+                        sequence,
+                        content,
+                        token_usage,
+                    } => {
+                        output.response(&agent, sequence, Some(&agent));
+                        if let Some(ref usage) = token_usage {
+                            output.usage(&agent, sequence, usage, &config.model, Some(&agent));
+                        }
+                        if let Err(e) =
+                            write_raw_response(&ctx, &agent, sequence, &content, token_usage.as_ref())
+                                .await
+                        {
+                            output.warning(&format!("failed to write response: {}", e));
+                        }
+                    }
+                    Response::ToolCall {
+                        agent,
+                        sequence,
+                        name,
+                        args,
+                        status,
+                    } => {
+                        if let Err(e) = write_tool_call(&ctx, &agent, sequence, &name, &args, &status).await
+                        {
+                            output.warning(&format!("failed to write tool call: {}", e));
+                        }
+                    }
+                    Response::Decision {
+                        action,
+                        reason,
+                        remaining_searches: searches,
+                        remaining_iterations: iterations,
+                    } => {
+                        output.decision(&action, &reason, searches, iterations, None);
+                        remaining_searches = Some(searches);
+                        remaining_iterations = Some(iterations);
+                    }
+                    Response::Report {
+                        short_summary,
+                        markdown_report,
+                        follow_up_questions,
+                    } => {
+                        ctx.markdown_report = Some(markdown_report.clone());
+                        output.report(&short_summary, &markdown_report, &follow_up_questions);
+                    }
+                    Response::Metadata {
+                        model,
+                        total_tokens,
+                        ..
+                    } => {
+                        ctx.model = Some(model);
+                        ctx.total_tokens = total_tokens;
+                    }
+                    Response::Error { message, code } => {
+                        output.error(code.as_deref(), &message, None);
+                    }
+                    Response::Done { success: s } => {
+                        success = s;
+                    }
+                    Response::StreamDelta {
+                        agent,
+                        sequence,
+                        text,
+                    } => {
+                        output.stream_delta(&agent, sequence, &text);
+                    }
+                    Response::StreamBegin { .. } | Response::StreamEnd { .. } => {
+                        // Only the deltas in between carry text worth emitting;
+                        // the finished message is reported via `response` once
+                        // its `AgentOutput` arrives.
                     }
                 }
-                Response::Decision {
-                    action,
-                    reason,
-                    remaining_searches,
-                    remaining_iterations,
-                } => {
-                    output.decision(&action, &reason, remaining_searches, remaining_iterations);
-                }
-                Response::Report {
-                    short_summary,
-                    markdown_report,
-                    follow_up_questions,
-                } => {
-                    ctx.markdown_report = Some(markdown_report.clone());
-                    output.report(&short_summary, &markdown_report, &follow_up_questions);
-                }
-                Response::Metadata {
-                    model,
-                    total_tokens,
-                    ..
-                } => {
-                    ctx.model = Some(model);
-                    ctx.total_tokens = total_tokens;
-                }
-                Response::Error { message, code } => {
-                    output.error(code.as_deref(), &message);
-                }
-                Response::Done { success: s } => {
-                    success = s;
-                }
-            },
+            }
             Err(e) => {
                 output.warning(&format!("failed to parse response: {} (line: {})", e, line));
             }
         }
     }
 
-    drop(stdin);
+    drop(writer);
 
-    let status = child.wait().await?;
+    let transport_ok = reader.finished_successfully().await?;
 
-    if let Some(ref markdown) = ctx.markdown_report
-        && let Err(e) = write_output(&run_dir, markdown).await
-    {
-        output.warning(&format!("failed to write output.md: {}", e));
+    if let Some(ref markdown) = ctx.markdown_report {
+        if let Err(e) = write_output(&run_dir, markdown).await {
+            output.warning(&format!("failed to write output.md: {}", e));
+        }
+        if let Some(format) = cli::export_format(cli) {
+            if let Err(e) = write_export(&run_dir, markdown, format).await {
+                output.warning(&format!("failed to write exported report: {}", e));
+            }
+        }
     }
 
     let metadata = ctx.to_metadata(run_id.clone());
@@ -452,9 +631,20 @@ async fn run_single_query(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
         output.warning(&format!("failed to write metadata.json: {}", e));
     }
 
-    output.complete(success && status.success(), &run_id, &run_dir);
+    let final_success = success && transport_ok;
+    metrics.record_run(
+        ctx.model.as_deref(),
+        final_success,
+        Duration::from_millis(ctx.elapsed_ms()),
+        ctx.total_tokens,
+        remaining_searches.map(|r| config.max_searches.saturating_sub(r)),
+        remaining_iterations.map(|r| config.max_iterations.saturating_sub(r)),
+        clarifying_rounds,
+    );
+
+    output.complete(final_success, &run_id, &run_dir);
 
-    if !status.success() || !success {
+    if !final_success {
         std::process::exit(1);
     }
 
@@ -0,0 +1,208 @@
+//! In-process run telemetry, exported on demand in Prometheus text
+//! exposition format from a lightweight HTTP server bound to
+//! `--metrics-addr`. Counters and histograms are always collected
+//! (`run_research_query`/`run_single_query` call `Metrics::record_run`
+//! once a run finishes, whether or not the server is running; a run that
+//! aborts with an error before then is not recorded); the server is only spawned when the user asks to
+//! scrape them, mirroring how `EventBus` is only served when
+//! `--event-socket` is set.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upper bounds (seconds) of the run-duration histogram's buckets, in the
+/// `le="..."` label Prometheus expects; `render` adds a final `+Inf`
+/// bucket on top of these so every observation lands in some bucket.
+const DURATION_BUCKETS_SECONDS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+#[derive(Default)]
+struct ModelMetrics {
+    runs_total: u64,
+    runs_success_total: u64,
+    runs_failure_total: u64,
+    tokens_total: u64,
+    searches_total: u64,
+    iterations_total: u64,
+    clarifying_rounds_total: u64,
+    duration_bucket_counts: [u64; DURATION_BUCKETS_SECONDS.len()],
+    duration_sum_seconds: f64,
+    duration_count: u64,
+}
+
+/// Counters and histograms for every run so far, labeled by `model` (or
+/// `"unknown"` for a run that errored before the model was known).
+pub struct Metrics {
+    by_model: Mutex<HashMap<String, ModelMetrics>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            by_model: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records one finished run's outcome: its model (or `"unknown"`),
+    /// success, end-to-end duration, cumulative tokens consumed (from the
+    /// `Response::Metadata` that arrived during the run), searches and
+    /// orchestrator iterations actually used (derived from the last
+    /// `Response::Decision`'s `remaining_*` fields), and how many
+    /// clarifying-question rounds it went through.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_run(
+        &self,
+        model: Option<&str>,
+        success: bool,
+        duration: Duration,
+        tokens: Option<u32>,
+        searches_used: Option<u32>,
+        iterations_used: Option<u32>,
+        clarifying_rounds: u32,
+    ) {
+        let model = model.unwrap_or("unknown").to_string();
+        let mut guard = self.by_model.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = guard.entry(model).or_default();
+
+        entry.runs_total += 1;
+        if success {
+            entry.runs_success_total += 1;
+        } else {
+            entry.runs_failure_total += 1;
+        }
+        entry.tokens_total += u64::from(tokens.unwrap_or(0));
+        entry.searches_total += u64::from(searches_used.unwrap_or(0));
+        entry.iterations_total += u64::from(iterations_used.unwrap_or(0));
+        entry.clarifying_rounds_total += u64::from(clarifying_rounds);
+
+        let seconds = duration.as_secs_f64();
+        entry.duration_sum_seconds += seconds;
+        entry.duration_count += 1;
+        for (bucket, upper_bound) in entry
+            .duration_bucket_counts
+            .iter_mut()
+            .zip(DURATION_BUCKETS_SECONDS)
+        {
+            if seconds <= *upper_bound {
+                *bucket += 1;
+            }
+        }
+    }
+
+    /// Renders every series in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let guard = self.by_model.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        out.push_str("# HELP lode_runs_total Research runs finished, by model and outcome; runs that abort with an error before finishing are not counted.\n");
+        out.push_str("# TYPE lode_runs_total counter\n");
+        for (model, m) in guard.iter() {
+            out.push_str(&format!(
+                "lode_runs_total{{model=\"{model}\",success=\"true\"}} {}\n",
+                m.runs_success_total
+            ));
+            out.push_str(&format!(
+                "lode_runs_total{{model=\"{model}\",success=\"false\"}} {}\n",
+                m.runs_failure_total
+            ));
+        }
+
+        out.push_str("# HELP lode_tokens_total Tokens consumed, by model.\n");
+        out.push_str("# TYPE lode_tokens_total counter\n");
+        for (model, m) in guard.iter() {
+            out.push_str(&format!(
+                "lode_tokens_total{{model=\"{model}\"}} {}\n",
+                m.tokens_total
+            ));
+        }
+
+        out.push_str("# HELP lode_searches_total Web searches performed, by model.\n");
+        out.push_str("# TYPE lode_searches_total counter\n");
+        for (model, m) in guard.iter() {
+            out.push_str(&format!(
+                "lode_searches_total{{model=\"{model}\"}} {}\n",
+                m.searches_total
+            ));
+        }
+
+        out.push_str("# HELP lode_iterations_total Orchestrator reasoning loops used, by model.\n");
+        out.push_str("# TYPE lode_iterations_total counter\n");
+        for (model, m) in guard.iter() {
+            out.push_str(&format!(
+                "lode_iterations_total{{model=\"{model}\"}} {}\n",
+                m.iterations_total
+            ));
+        }
+
+        out.push_str("# HELP lode_clarifying_rounds_total Clarifying-question rounds, by model.\n");
+        out.push_str("# TYPE lode_clarifying_rounds_total counter\n");
+        for (model, m) in guard.iter() {
+            out.push_str(&format!(
+                "lode_clarifying_rounds_total{{model=\"{model}\"}} {}\n",
+                m.clarifying_rounds_total
+            ));
+        }
+
+        out.push_str("# HELP lode_run_duration_seconds End-to-end run duration, by model.\n");
+        out.push_str("# TYPE lode_run_duration_seconds histogram\n");
+        for (model, m) in guard.iter() {
+            for (upper_bound, count) in DURATION_BUCKETS_SECONDS.iter().zip(&m.duration_bucket_counts) {
+                out.push_str(&format!(
+                    "lode_run_duration_seconds_bucket{{model=\"{model}\",le=\"{upper_bound}\"}} {count}\n"
+                ));
+            }
+            out.push_str(&format!(
+                "lode_run_duration_seconds_bucket{{model=\"{model}\",le=\"+Inf\"}} {}\n",
+                m.duration_count
+            ));
+            out.push_str(&format!(
+                "lode_run_duration_seconds_sum{{model=\"{model}\"}} {}\n",
+                m.duration_sum_seconds
+            ));
+            out.push_str(&format!(
+                "lode_run_duration_seconds_count{{model=\"{model}\"}} {}\n",
+                m.duration_count
+            ));
+        }
+
+        out
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serves `metrics.render()` as `text/plain` to every connection accepted
+/// on `addr`, regardless of the request path or method — there's only one
+/// thing to scrape, so there's no routing to do. Runs until it hits an
+/// accept error; callers typically `tokio::spawn` this alongside the TUI
+/// or single-query run, and it shuts down with the rest of the process.
+pub async fn serve(metrics: std::sync::Arc<Metrics>, addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            // Discard the request; we only ever serve one document.
+            let _ = stream.read(&mut buf).await;
+
+            let body = metrics.render();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
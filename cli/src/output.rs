@@ -1,8 +1,15 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use serde::Serialize;
 
 use crate::cli::RequestConfig;
+use crate::event_bus::EventBus;
+use crate::pricing;
+use crate::protocol::TokenUsage;
 
 #[derive(Clone, Copy)]
 pub enum OutputMode {
@@ -11,8 +18,35 @@ pub enum OutputMode {
     Json,
 }
 
+/// Emits run progress as either human-readable lines or NDJSON records, and
+/// optionally tees every event to an `events.ndjson` artifacts file.
+///
+/// Writes go through `Mutex`-guarded `BufWriter`s, so `Output` is
+/// `Send + Sync` and safe to share behind an `Arc` across
+/// concurrently-dispatched sub-agents without interleaving partial lines
+/// into the NDJSON stream. Each record's monotonic `seq` is taken from an
+/// atomic counter when the record is built, before any writer lock is
+/// held, so records from concurrent callers can reach a writer in a
+/// different order from their `seq`. Callers that know
+/// which sub-agent/step an event belongs to can pass a `correlation_id`,
+/// recorded alongside `seq` so a consumer can reassemble which events
+/// belong to which concurrent branch.
+///
+/// Each event record is serialized once and fanned out to the event log
+/// (always, regardless of `mode`) and to the console (as JSON in `Json`
+/// mode, as a human-readable line in `Human` mode, or not at all in
+/// `Quiet` mode). This decouples what a human sees on the terminal from
+/// the canonical, replayable record captured for tooling.
 pub struct Output {
     mode: OutputMode,
+    stdout: Mutex<BufWriter<Box<dyn Write + Send>>>,
+    stderr: Mutex<BufWriter<Box<dyn Write + Send>>>,
+    event_log: Option<Mutex<BufWriter<File>>>,
+    event_bus: Option<EventBus>,
+    seq: AtomicU64,
+    /// Cumulative `(total_tokens, total_cost_usd)` across every `usage`
+    /// event so far, folded into the final `complete` event.
+    totals: Mutex<(u64, f64)>,
 }
 
 impl Output {
@@ -24,134 +58,270 @@ impl Output {
         } else {
             OutputMode::Human
         };
-        Self { mode }
+        Self {
+            mode,
+            stdout: Mutex::new(BufWriter::new(Box::new(io::stdout()))),
+            stderr: Mutex::new(BufWriter::new(Box::new(io::stderr()))),
+            event_log: None,
+            event_bus: None,
+            seq: AtomicU64::new(0),
+            totals: Mutex::new((0, 0.0)),
+        }
+    }
+
+    /// Builds an `Output` in `Json` mode whose stdout/stderr both write into
+    /// a shared in-memory buffer instead of the real streams, so a test can
+    /// drive a scripted run and inspect the emitted NDJSON afterwards.
+    #[cfg(test)]
+    pub fn new_for_test() -> (Self, std::sync::Arc<Mutex<Vec<u8>>>) {
+        let buf = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let output = Self {
+            mode: OutputMode::Json,
+            stdout: Mutex::new(BufWriter::new(Box::new(SharedBuf(buf.clone())))),
+            stderr: Mutex::new(BufWriter::new(Box::new(SharedBuf(buf.clone())))),
+            event_log: None,
+            event_bus: None,
+            seq: AtomicU64::new(0),
+            totals: Mutex::new((0, 0.0)),
+        };
+        (output, buf)
+    }
+
+    /// Tees every emitted event, as NDJSON, to `path` (typically
+    /// `<artifacts_dir>/events.ndjson`) in addition to the console. This
+    /// gives a canonical record of a run independent of `OutputMode`, even
+    /// for a `Human` or `Quiet` run. `--replay` does not read this file; it
+    /// reads the run's `responses.jsonl` instead.
+    pub fn with_event_log(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.event_log = Some(Mutex::new(BufWriter::new(file)));
+        Ok(self)
+    }
+
+    /// Fans every emitted event out to `bus` as well, so external
+    /// subscribers connected to its socket see the same `prompt`/
+    /// `response`/`decision` records live, independent of `OutputMode`.
+    pub fn with_event_bus(mut self, bus: EventBus) -> Self {
+        self.event_bus = Some(bus);
+        self
+    }
+
+    fn next_seq(&self) -> u64 {
+        self.seq.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Writes one line and flushes immediately, so a piped NDJSON consumer
+    /// sees each record as soon as it's emitted rather than waiting on the
+    /// `BufWriter`'s internal buffer to fill.
+    fn write_stdout(&self, line: &str) {
+        let mut guard = self.stdout.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(guard, "{}", line);
+        let _ = guard.flush();
+    }
+
+    fn write_stderr(&self, line: &str) {
+        let mut guard = self.stderr.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(guard, "{}", line);
+        let _ = guard.flush();
+    }
+
+    /// Appends a serialized event record to the event log, if one is
+    /// configured, flushing after each line for the same replayability
+    /// reasons as `write_stdout`.
+    fn log_event(&self, json_line: &str) {
+        if let Some(log) = &self.event_log {
+            let mut guard = log.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = writeln!(guard, "{}", json_line);
+            let _ = guard.flush();
+        }
+        if let Some(bus) = &self.event_bus {
+            bus.publish(json_line);
+        }
     }
 
     pub fn start(&self, run_id: &str, artifacts_dir: &Path, config: &RequestConfig) {
+        #[derive(Serialize)]
+        struct Start<'a> {
+            r#type: &'static str,
+            seq: u64,
+            version: &'static str,
+            run_id: &'a str,
+            artifacts_dir: &'a str,
+            model: &'a str,
+            search_count: u32,
+            max_iterations: u32,
+            max_searches: u32,
+            auto_decide: bool,
+        }
+        let msg = Start {
+            r#type: "start",
+            seq: self.next_seq(),
+            version: "v1",
+            run_id,
+            artifacts_dir: &artifacts_dir.display().to_string(),
+            model: &config.model,
+            search_count: config.search_count,
+            max_iterations: config.max_iterations,
+            max_searches: config.max_searches,
+            auto_decide: config.auto_decide,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        if let Some(bus) = &self.event_bus {
+            bus.set_handshake(line.clone());
+        }
+        self.log_event(&line);
+
         match self.mode {
             OutputMode::Human => {
-                eprintln!("Starting research run: {}", run_id);
-                eprintln!(
+                self.write_stderr(&format!("Starting research run: {}", run_id));
+                self.write_stderr(&format!(
                     "Model: {}, Searches: {} (max: {}), Iterations: {}",
                     config.model, config.search_count, config.max_searches, config.max_iterations
-                );
-                eprintln!("Artifacts: {}", artifacts_dir.display());
+                ));
+                self.write_stderr(&format!("Artifacts: {}", artifacts_dir.display()));
             }
             OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Start<'a> {
-                    r#type: &'static str,
-                    version: &'static str,
-                    run_id: &'a str,
-                    artifacts_dir: &'a str,
-                    model: &'a str,
-                    search_count: u32,
-                    max_iterations: u32,
-                    max_searches: u32,
-                    auto_decide: bool,
-                }
-                let msg = Start {
-                    r#type: "start",
-                    version: "v1",
-                    run_id,
-                    artifacts_dir: &artifacts_dir.display().to_string(),
-                    model: &config.model,
-                    search_count: config.search_count,
-                    max_iterations: config.max_iterations,
-                    max_searches: config.max_searches,
-                    auto_decide: config.auto_decide,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
-            }
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
-    pub fn status(&self, message: &str) {
+    pub fn status(&self, message: &str, correlation_id: Option<&str>) {
+        #[derive(Serialize)]
+        struct Status<'a> {
+            r#type: &'static str,
+            seq: u64,
+            message: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            correlation_id: Option<&'a str>,
+        }
+        let msg = Status {
+            r#type: "status",
+            seq: self.next_seq(),
+            message,
+            correlation_id,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
-            OutputMode::Human => eprintln!("â†’ {}", message),
+            OutputMode::Human => self.write_stderr(&format!("→ {}", message)),
             OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Status<'a> {
-                    r#type: &'static str,
-                    message: &'a str,
-                }
-                let msg = Status {
-                    r#type: "status",
-                    message,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
-            }
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
     pub fn trace(&self, trace_id: &str, trace_url: &str) {
+        #[derive(Serialize)]
+        struct Trace<'a> {
+            r#type: &'static str,
+            seq: u64,
+            trace_id: &'a str,
+            trace_url: &'a str,
+        }
+        let msg = Trace {
+            r#type: "trace",
+            seq: self.next_seq(),
+            trace_id,
+            trace_url,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
             OutputMode::Human => {
-                eprintln!(
-                    "ðŸ“Š Trace [{}]: {}",
+                self.write_stderr(&format!(
+                    "📊 Trace [{}]: {}",
                     &trace_id[..8.min(trace_id.len())],
                     trace_url
-                );
+                ));
             }
             OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Trace<'a> {
-                    r#type: &'static str,
-                    trace_id: &'a str,
-                    trace_url: &'a str,
-                }
-                let msg = Trace {
-                    r#type: "trace",
-                    trace_id,
-                    trace_url,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
-            }
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
-    pub fn prompt(&self, agent: &str, sequence: u32) {
+    pub fn prompt(&self, agent: &str, sequence: u32, correlation_id: Option<&str>) {
+        #[derive(Serialize)]
+        struct Prompt<'a> {
+            r#type: &'static str,
+            seq: u64,
+            agent: &'a str,
+            sequence: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            correlation_id: Option<&'a str>,
+        }
+        let msg = Prompt {
+            r#type: "prompt",
+            seq: self.next_seq(),
+            agent,
+            sequence,
+            correlation_id,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
-            OutputMode::Human => eprintln!("ðŸ“ Prompt: {} ({})", agent, sequence),
+            OutputMode::Human => self.write_stderr(&format!("📝 Prompt: {} ({})", agent, sequence)),
             OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Prompt<'a> {
-                    r#type: &'static str,
-                    agent: &'a str,
-                    sequence: u32,
-                }
-                let msg = Prompt {
-                    r#type: "prompt",
-                    agent,
-                    sequence,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
-            }
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
-    pub fn response(&self, agent: &str, sequence: u32) {
+    pub fn response(&self, agent: &str, sequence: u32, correlation_id: Option<&str>) {
+        #[derive(Serialize)]
+        struct Response<'a> {
+            r#type: &'static str,
+            seq: u64,
+            agent: &'a str,
+            sequence: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            correlation_id: Option<&'a str>,
+        }
+        let msg = Response {
+            r#type: "response",
+            seq: self.next_seq(),
+            agent,
+            sequence,
+            correlation_id,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
-            OutputMode::Human => eprintln!("ðŸ“¥ Response: {} ({})", agent, sequence),
-            OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Response<'a> {
-                    r#type: &'static str,
-                    agent: &'a str,
-                    sequence: u32,
-                }
-                let msg = Response {
-                    r#type: "response",
-                    agent,
-                    sequence,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
+            OutputMode::Human => {
+                self.write_stderr(&format!("📥 Response: {} ({})", agent, sequence))
             }
+            OutputMode::Quiet => {}
+            OutputMode::Json => self.write_stdout(&line),
+        }
+    }
+
+    /// Emits one partial chunk of `agent`/`sequence`'s in-progress output.
+    /// Only meaningful in `Json` mode, where a consumer reassembles the
+    /// full text by concatenating `text` across deltas with the same
+    /// `agent`/`sequence`; `Human`/`Quiet` wait for the finished `response`
+    /// event instead, so this is a no-op there.
+    pub fn stream_delta(&self, agent: &str, sequence: u32, text: &str) {
+        #[derive(Serialize)]
+        struct StreamDelta<'a> {
+            r#type: &'static str,
+            seq: u64,
+            agent: &'a str,
+            sequence: u32,
+            text: &'a str,
+        }
+        let msg = StreamDelta {
+            r#type: "stream_delta",
+            seq: self.next_seq(),
+            agent,
+            sequence,
+            text,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
+        if let OutputMode::Json = self.mode {
+            self.write_stdout(&line);
         }
     }
 
@@ -161,34 +331,103 @@ impl Output {
         reason: &str,
         remaining_searches: u32,
         remaining_iterations: u32,
+        correlation_id: Option<&str>,
     ) {
+        #[derive(Serialize)]
+        struct Decision<'a> {
+            r#type: &'static str,
+            seq: u64,
+            action: &'a str,
+            reason: &'a str,
+            remaining_searches: u32,
+            remaining_iterations: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            correlation_id: Option<&'a str>,
+        }
+        let msg = Decision {
+            r#type: "decision",
+            seq: self.next_seq(),
+            action,
+            reason,
+            remaining_searches,
+            remaining_iterations,
+            correlation_id,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
             OutputMode::Human => {
-                eprintln!(
-                    "ðŸ¤” Decision: {} (searches: {}, iterations: {})",
+                self.write_stderr(&format!(
+                    "🤔 Decision: {} (searches: {}, iterations: {})",
                     action, remaining_searches, remaining_iterations
-                );
-                eprintln!("   Reason: {}", reason);
+                ));
+                self.write_stderr(&format!("   Reason: {}", reason));
             }
             OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Decision<'a> {
-                    r#type: &'static str,
-                    action: &'a str,
-                    reason: &'a str,
-                    remaining_searches: u32,
-                    remaining_iterations: u32,
-                }
-                let msg = Decision {
-                    r#type: "decision",
-                    action,
-                    reason,
-                    remaining_searches,
-                    remaining_iterations,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
+            OutputMode::Json => self.write_stdout(&line),
+        }
+    }
+
+    /// Records token usage for one prompt/response round-trip, estimating
+    /// its USD cost from `model` via the `pricing` table and folding both
+    /// into the cumulative totals reported by `complete`.
+    pub fn usage(
+        &self,
+        agent: &str,
+        sequence: u32,
+        token_usage: &TokenUsage,
+        model: &str,
+        correlation_id: Option<&str>,
+    ) {
+        let cost_usd = pricing::estimate_cost_usd(
+            model,
+            token_usage.prompt_tokens,
+            token_usage.completion_tokens,
+        );
+
+        {
+            let mut totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+            totals.0 += token_usage.total_tokens as u64;
+            if let Some(cost) = cost_usd {
+                totals.1 += cost;
+            }
+        }
+
+        #[derive(Serialize)]
+        struct Usage<'a> {
+            r#type: &'static str,
+            seq: u64,
+            agent: &'a str,
+            sequence: u32,
+            prompt_tokens: u32,
+            completion_tokens: u32,
+            total_tokens: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            cost_usd: Option<f64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            correlation_id: Option<&'a str>,
+        }
+        let msg = Usage {
+            r#type: "usage",
+            seq: self.next_seq(),
+            agent,
+            sequence,
+            prompt_tokens: token_usage.prompt_tokens,
+            completion_tokens: token_usage.completion_tokens,
+            total_tokens: token_usage.total_tokens,
+            cost_usd,
+            correlation_id,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
+        match self.mode {
+            OutputMode::Human => {
+                self.write_stderr(&format_usage_line(token_usage.total_tokens as u64, cost_usd));
             }
+            OutputMode::Quiet => {}
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
@@ -198,109 +437,292 @@ impl Output {
         markdown_report: &str,
         follow_up_questions: &[String],
     ) {
+        #[derive(Serialize)]
+        struct Report<'a> {
+            r#type: &'static str,
+            seq: u64,
+            short_summary: &'a str,
+            markdown_report: &'a str,
+            follow_up_questions: &'a [String],
+        }
+        let msg = Report {
+            r#type: "report",
+            seq: self.next_seq(),
+            short_summary,
+            markdown_report,
+            follow_up_questions,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
             OutputMode::Human | OutputMode::Quiet => {
-                println!("\n{}\n", "=".repeat(60));
-                println!("SUMMARY: {}\n", short_summary);
-                println!("{}", markdown_report);
+                self.write_stdout(&format!("\n{}\n", "=".repeat(60)));
+                self.write_stdout(&format!("SUMMARY: {}\n", short_summary));
+                self.write_stdout(markdown_report);
                 if !follow_up_questions.is_empty() {
-                    println!("\nFollow-up questions:");
+                    self.write_stdout("\nFollow-up questions:");
                     for q in follow_up_questions {
-                        println!("  - {}", q);
+                        self.write_stdout(&format!("  - {}", q));
                     }
                 }
             }
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Report<'a> {
-                    r#type: &'static str,
-                    short_summary: &'a str,
-                    markdown_report: &'a str,
-                    follow_up_questions: &'a [String],
-                }
-                let msg = Report {
-                    r#type: "report",
-                    short_summary,
-                    markdown_report,
-                    follow_up_questions,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
-            }
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
-    pub fn error(&self, code: Option<&str>, message: &str) {
+    pub fn error(&self, code: Option<&str>, message: &str, correlation_id: Option<&str>) {
+        #[derive(Serialize)]
+        struct Error<'a> {
+            r#type: &'static str,
+            seq: u64,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            code: Option<&'a str>,
+            message: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            correlation_id: Option<&'a str>,
+        }
+        let msg = Error {
+            r#type: "error",
+            seq: self.next_seq(),
+            code,
+            message,
+            correlation_id,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
             OutputMode::Human | OutputMode::Quiet => {
                 if let Some(c) = code {
-                    eprintln!("Error [{}]: {}", c, message);
+                    self.write_stderr(&format!("Error [{}]: {}", c, message));
                 } else {
-                    eprintln!("Error: {}", message);
+                    self.write_stderr(&format!("Error: {}", message));
                 }
             }
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Error<'a> {
-                    r#type: &'static str,
-                    #[serde(skip_serializing_if = "Option::is_none")]
-                    code: Option<&'a str>,
-                    message: &'a str,
-                }
-                let msg = Error {
-                    r#type: "error",
-                    code,
-                    message,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
-            }
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
     pub fn warning(&self, message: &str) {
+        #[derive(Serialize)]
+        struct Warning<'a> {
+            r#type: &'static str,
+            seq: u64,
+            message: &'a str,
+        }
+        let msg = Warning {
+            r#type: "warning",
+            seq: self.next_seq(),
+            message,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
-            OutputMode::Human => eprintln!("Warning: {}", message),
+            OutputMode::Human => self.write_stderr(&format!("Warning: {}", message)),
             OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Warning<'a> {
-                    r#type: &'static str,
-                    message: &'a str,
-                }
-                let msg = Warning {
-                    r#type: "warning",
-                    message,
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
-            }
+            OutputMode::Json => self.write_stdout(&line),
         }
     }
 
     pub fn complete(&self, success: bool, run_id: &str, artifacts_dir: &Path) {
+        let (total_tokens, total_cost_usd) = {
+            let totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+            (totals.0, totals.1)
+        };
+        let total_tokens = if total_tokens > 0 { Some(total_tokens) } else { None };
+        let total_cost_usd = if total_cost_usd > 0.0 {
+            Some(total_cost_usd)
+        } else {
+            None
+        };
+
+        #[derive(Serialize)]
+        struct Complete<'a> {
+            r#type: &'static str,
+            seq: u64,
+            success: bool,
+            run_id: &'a str,
+            artifacts_dir: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            total_tokens: Option<u64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            total_cost_usd: Option<f64>,
+        }
+        let msg = Complete {
+            r#type: "complete",
+            seq: self.next_seq(),
+            success,
+            run_id,
+            artifacts_dir: &artifacts_dir.display().to_string(),
+            total_tokens,
+            total_cost_usd,
+        };
+        let line = serde_json::to_string(&msg).unwrap();
+        self.log_event(&line);
+
         match self.mode {
             OutputMode::Human => {
-                eprintln!(
+                self.write_stderr(&format!(
                     "Run complete. Artifacts saved to: {}",
                     artifacts_dir.display()
-                );
-            }
-            OutputMode::Quiet => {}
-            OutputMode::Json => {
-                #[derive(Serialize)]
-                struct Complete<'a> {
-                    r#type: &'static str,
-                    success: bool,
-                    run_id: &'a str,
-                    artifacts_dir: &'a str,
+                ));
+                if let Some(tokens) = total_tokens {
+                    self.write_stderr(&format!("   {}", format_usage_line(tokens, total_cost_usd)));
                 }
-                let msg = Complete {
-                    r#type: "complete",
-                    success,
-                    run_id,
-                    artifacts_dir: &artifacts_dir.display().to_string(),
-                };
-                println!("{}", serde_json::to_string(&msg).unwrap());
             }
+            OutputMode::Quiet => {}
+            OutputMode::Json => self.write_stdout(&line),
+        }
+    }
+}
+
+/// Formats a compact human-readable usage line, e.g. `💰 1,240 tok (~$0.004)`.
+fn format_usage_line(tokens: u64, cost_usd: Option<f64>) -> String {
+    match cost_usd {
+        Some(cost) => format!("💰 {} tok (~${:.3})", format_with_commas(tokens), cost),
+        None => format!("💰 {} tok", format_with_commas(tokens)),
+    }
+}
+
+fn format_with_commas(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().rev().enumerate() {
+        if i > 0 && i % 3 == 0 {
+            grouped.push(',');
         }
+        grouped.push(c);
     }
+    grouped.chars().rev().collect()
 }
 
+/// Writes into a buffer shared with the test that created it, so the test
+/// can inspect what `Output` wrote after the scripted run completes.
+#[cfg(test)]
+struct SharedBuf(std::sync::Arc<Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Golden-file-style regression test for the JSON event stream: drives a
+/// scripted run through `Output`'s emit methods and checks the exact
+/// sequence and shape of the resulting NDJSON lines. Each expected line is
+/// a `serde_json::Value`; the string `"*"` in any field matches any actual
+/// value there, for volatile fields like `run_id`/`trace_id`/`artifacts_dir`
+/// that a real run fills in with a generated UUID or temp path. Expected
+/// objects only need to name the fields they care about -- extra fields in
+/// the actual record (e.g. `artifacts_dir` on `start`, which the expected
+/// values below leave out) don't fail the match. This locks down the `version: "v1"` event contract so future
+/// edits to any emit method can't silently change it out from under
+/// downstream consumers.
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::{Value, json};
+
+    use super::Output;
+    use crate::cli::RequestConfig;
+
+    fn value_matches(expected: &Value, actual: &Value) -> bool {
+        match expected {
+            Value::String(s) if s == "*" => true,
+            Value::Object(exp_map) => match actual {
+                Value::Object(act_map) => exp_map
+                    .iter()
+                    .all(|(k, v)| act_map.get(k).is_some_and(|a| value_matches(v, a))),
+                _ => false,
+            },
+            Value::Array(exp_arr) => match actual {
+                Value::Array(act_arr) if exp_arr.len() == act_arr.len() => {
+                    exp_arr.iter().zip(act_arr).all(|(e, a)| value_matches(e, a))
+                }
+                _ => false,
+            },
+            other => other == actual,
+        }
+    }
+
+    /// Fails with the first mismatching line (expected vs. actual), or on an
+    /// event-count mismatch.
+    fn assert_events_match(raw: &str, expected: &[Value]) {
+        let actual_lines: Vec<&str> = raw.lines().collect();
+        assert_eq!(
+            actual_lines.len(),
+            expected.len(),
+            "event count mismatch: expected {}, got {}",
+            expected.len(),
+            actual_lines.len()
+        );
+
+        for (i, (line, expected_value)) in actual_lines.iter().zip(expected).enumerate() {
+            let actual_value: Value = serde_json::from_str(line)
+                .unwrap_or_else(|e| panic!("line {i}: invalid JSON ({e}): {line}"));
+            assert!(
+                value_matches(expected_value, &actual_value),
+                "line {i} mismatch:\n  expected: {expected_value}\n  actual:   {actual_value}"
+            );
+        }
+    }
+
+    #[test]
+    fn json_event_stream_matches_golden_shape() {
+        let (output, buf) = Output::new_for_test();
+        let config = RequestConfig {
+            model: "gpt-4o".to_string(),
+            search_count: 5,
+            max_iterations: 10,
+            max_searches: 15,
+            auto_decide: true,
+        };
+
+        output.start("run-1", Path::new("runs/run-1"), &config);
+        output.status("planning searches", None);
+        output.prompt("Planner", 1, Some("Planner"));
+        output.response("Planner", 1, Some("Planner"));
+        output.decision("search", "need more data", 10, 9, None);
+        output.report("done", "# Report\n", &[]);
+        output.complete(true, "run-1", Path::new("runs/run-1"));
+
+        let expected = vec![
+            json!({
+                "type": "start", "seq": 0, "version": "v1", "run_id": "*",
+                "model": "gpt-4o", "search_count": 5, "max_iterations": 10,
+                "max_searches": 15, "auto_decide": true,
+            }),
+            json!({"type": "status", "seq": 1, "message": "planning searches"}),
+            json!({
+                "type": "prompt", "seq": 2, "agent": "Planner", "sequence": 1,
+                "correlation_id": "Planner",
+            }),
+            json!({
+                "type": "response", "seq": 3, "agent": "Planner", "sequence": 1,
+                "correlation_id": "Planner",
+            }),
+            json!({
+                "type": "decision", "seq": 4, "action": "search",
+                "reason": "need more data", "remaining_searches": 10,
+                "remaining_iterations": 9,
+            }),
+            json!({
+                "type": "report", "seq": 5, "short_summary": "done",
+                "markdown_report": "# Report\n", "follow_up_questions": [],
+            }),
+            json!({"type": "complete", "seq": 6, "success": true, "run_id": "*"}),
+        ];
+
+        let raw = String::from_utf8(buf.lock().unwrap().clone()).unwrap();
+        assert_events_match(&raw, &expected);
+    }
+}
@@ -0,0 +1,21 @@
+//! Per-model USD pricing, used by `Output::usage` to estimate the cost of a
+//! prompt/completion round-trip from its token counts. Prices are USD per
+//! 1,000 tokens. Unknown models simply have no entry, so callers get `None`
+//! back rather than a guessed cost.
+
+/// (model name, prompt $ / 1K tokens, completion $ / 1K tokens)
+const PRICING_TABLE: &[(&str, f64, f64)] = &[
+    ("gpt-4o", 0.0025, 0.01),
+    ("gpt-4o-mini", 0.00015, 0.0006),
+    ("gpt-4-turbo", 0.01, 0.03),
+    ("gpt-3.5-turbo", 0.0005, 0.0015),
+];
+
+/// Estimates the USD cost of a round-trip for `model`, or `None` if `model`
+/// isn't in the pricing table.
+pub fn estimate_cost_usd(model: &str, prompt_tokens: u32, completion_tokens: u32) -> Option<f64> {
+    let &(_, prompt_price, completion_price) =
+        PRICING_TABLE.iter().find(|(name, _, _)| *name == model)?;
+    Some((prompt_tokens as f64 / 1000.0) * prompt_price
+        + (completion_tokens as f64 / 1000.0) * completion_price)
+}
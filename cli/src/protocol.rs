@@ -28,6 +28,14 @@ pub struct TokenUsage {
     pub total_tokens: u32,
 }
 
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ToolCallStatus {
+    Running,
+    Success,
+    Error { message: String },
+}
+
 #[derive(Clone, Copy, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum InterruptCommand {
@@ -84,6 +92,31 @@ pub enum Response {
         content: String,
         token_usage: Option<TokenUsage>,
     },
+    /// Marks the start of a token-by-token `AgentOutput` for `agent`/`sequence`;
+    /// the final, complete text still arrives in the matching `AgentOutput`.
+    StreamBegin {
+        agent: String,
+        sequence: u32,
+    },
+    /// A partial chunk of `agent`/`sequence`'s in-progress output.
+    StreamDelta {
+        agent: String,
+        sequence: u32,
+        text: String,
+    },
+    /// Signals that `agent`/`sequence` has finished streaming.
+    StreamEnd {
+        agent: String,
+        sequence: u32,
+    },
+    ToolCall {
+        agent: String,
+        sequence: u32,
+        name: String,
+        args: String,
+        #[serde(flatten)]
+        status: ToolCallStatus,
+    },
     Decision {
         action: String,
         reason: String,
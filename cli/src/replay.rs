@@ -0,0 +1,127 @@
+//! `--replay <run_id>` reconstructs a completed run from its persisted
+//! artifacts instead of reconnecting to the backend: the TUI reopens
+//! straight into that run (the same rehydration the Ctrl+R run browser
+//! uses), while `--json`/`--quiet` replays the run's `responses.jsonl`
+//! transcript (see `run::append_response`) back through the same `Output`
+//! calls `run_single_query` makes live, for identical rendering. Neither
+//! path spends backend tokens, so this is free to use for debugging,
+//! demos, or sharing a run's output offline.
+
+use std::path::{Path, PathBuf};
+
+use crate::cli::{self, Cli, load_config};
+use crate::output::Output;
+use crate::protocol::Response;
+use crate::run::read_responses;
+use crate::tui::{App, Tui};
+
+pub async fn run_replay(
+    cli: &Cli,
+    run_id: &str,
+    logs_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if cli.json || cli.quiet {
+        replay_to_output(cli, run_id).await
+    } else {
+        replay_to_tui(cli, logs_dir, run_id).await
+    }
+}
+
+async fn replay_to_output(cli: &Cli, run_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let run_dir = PathBuf::from("runs").join(run_id);
+    let responses = read_responses(&run_dir).await?;
+    let config = load_config(cli);
+    let output = Output::new(cli.json, cli.quiet);
+    output.start(run_id, &run_dir, &config);
+
+    let mut success = false;
+    for response in responses {
+        match response {
+            Response::Status { message } => output.status(&message, None),
+            Response::Trace {
+                trace_id,
+                trace_url,
+            } => output.trace(&trace_id, &trace_url),
+            Response::ClarifyingQuestions { .. } => {
+                // Already answered live; nothing to prompt for on replay.
+            }
+            Response::Prompt { agent, sequence, .. } => {
+                output.prompt(&agent, sequence, Some(&agent));
+            }
+            Response::AgentOutput {
+                agent,
+                sequence,
+                token_usage,
+                ..
+            } => {
+                output.response(&agent, sequence, Some(&agent));
+                if let Some(ref usage) = token_usage {
+                    output.usage(&agent, sequence, usage, &config.model, Some(&agent));
+                }
+            }
+            Response::ToolCall { .. } => {
+                // Tool call artifacts were already written on the live run;
+                // replay only re-renders what `Output` itself reports.
+            }
+            Response::Decision {
+                action,
+                reason,
+                remaining_searches,
+                remaining_iterations,
+            } => {
+                output.decision(&action, &reason, remaining_searches, remaining_iterations, None);
+            }
+            Response::Report {
+                short_summary,
+                markdown_report,
+                follow_up_questions,
+            } => {
+                output.report(&short_summary, &markdown_report, &follow_up_questions);
+            }
+            Response::Metadata { .. } => {}
+            Response::Error { message, code } => output.error(code.as_deref(), &message, None),
+            Response::Done { success: s } => success = s,
+            Response::StreamDelta {
+                agent,
+                sequence,
+                text,
+            } => output.stream_delta(&agent, sequence, &text),
+            Response::StreamBegin { .. } | Response::StreamEnd { .. } => {}
+        }
+    }
+
+    output.complete(success, run_id, &run_dir);
+    Ok(())
+}
+
+async fn replay_to_tui(
+    cli: &Cli,
+    logs_dir: &Path,
+    run_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = load_config(cli);
+    let theme_config = cli::load_theme_config(cli);
+    let mut app = App::new(!cli.no_auto, config.model.clone(), theme_config);
+    app.log_path = Some(logs_dir.display().to_string());
+    app.load_persisted_run(run_id).await?;
+
+    let mut tui_instance = Tui::new()?;
+    tui_instance
+        .run(
+            &mut app,
+            move |_query| {
+                // Replay is read-only; a fresh query would need `lode` run
+                // normally rather than `--replay`.
+            },
+            move |_answers, _is_final| {},
+            move || {},
+            move |markdown, saved_run_id| {
+                tokio::spawn(async move {
+                    let _ = crate::run::write_transcript(&markdown, saved_run_id.as_deref()).await;
+                });
+            },
+        )
+        .await?;
+
+    Ok(())
+}
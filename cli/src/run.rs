@@ -3,8 +3,10 @@ use std::time::Instant;
 
 use serde::Serialize;
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 
-use crate::protocol::{Request, RunMetadata, TokenUsage};
+use crate::export::{self, ExportFormat};
+use crate::protocol::{Request, Response, RunMetadata, TokenUsage, ToolCallStatus};
 
 pub struct RunContext {
     pub run_dir: PathBuf,
@@ -71,6 +73,10 @@ pub async fn write_request(
     Ok(())
 }
 
+#[tracing::instrument(
+    skip(ctx, content),
+    fields(trace_id = ctx.trace_id.as_deref().unwrap_or("-"), elapsed_ms = ctx.elapsed_ms())
+)]
 pub async fn write_prompt(
     ctx: &RunContext,
     agent: &str,
@@ -80,9 +86,18 @@ pub async fn write_prompt(
     let filename = format!("{:03}-{}.txt", sequence, agent.to_lowercase());
     let path = ctx.prompts_dir().join(filename);
     fs::write(path, content).await?;
+    tracing::debug!(%filename, "wrote prompt");
     Ok(())
 }
 
+#[tracing::instrument(
+    skip(ctx, content, token_usage),
+    fields(
+        trace_id = ctx.trace_id.as_deref().unwrap_or("-"),
+        elapsed_ms = ctx.elapsed_ms(),
+        total_tokens = token_usage.map(|t| t.total_tokens),
+    )
+)]
 pub async fn write_raw_response(
     ctx: &RunContext,
     agent: &str,
@@ -110,6 +125,45 @@ pub async fn write_raw_response(
     };
     let json = serde_json::to_string_pretty(&data)?;
     fs::write(path, json).await?;
+    tracing::debug!(%filename, "wrote agent response");
+    Ok(())
+}
+
+#[tracing::instrument(
+    skip(ctx, args, status),
+    fields(trace_id = ctx.trace_id.as_deref().unwrap_or("-"), elapsed_ms = ctx.elapsed_ms())
+)]
+pub async fn write_tool_call(
+    ctx: &RunContext,
+    agent: &str,
+    sequence: u32,
+    name: &str,
+    args: &str,
+    status: &ToolCallStatus,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let filename = format!("{:03}-{}-tool-{}.json", sequence, agent.to_lowercase(), name);
+    let path = ctx.responses_dir().join(filename);
+
+    #[derive(Serialize)]
+    struct ToolCallFile<'a> {
+        agent: &'a str,
+        sequence: u32,
+        name: &'a str,
+        args: &'a str,
+        #[serde(flatten)]
+        status: &'a ToolCallStatus,
+    }
+
+    let data = ToolCallFile {
+        agent,
+        sequence,
+        name,
+        args,
+        status,
+    };
+    let json = serde_json::to_string_pretty(&data)?;
+    fs::write(path, json).await?;
+    tracing::debug!(%filename, "wrote tool call");
     Ok(())
 }
 
@@ -122,6 +176,70 @@ pub async fn write_output(
     Ok(())
 }
 
+/// Writes the completed report re-serialized into `format`, alongside the
+/// canonical `output.md` written by `write_output`.
+pub async fn write_export(
+    run_dir: &Path,
+    markdown: &str,
+    format: ExportFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = run_dir.join(format!("output.{}", format.extension()));
+    let content = export::export(markdown, format);
+    fs::write(path, content).await?;
+    Ok(())
+}
+
+/// Writes a transcript exported from the TUI's `Ctrl+S` keybinding to
+/// `exports/`, named with the current Unix timestamp and, when known, the
+/// `run_id` of the session it came from so the file can be traced back to it.
+pub async fn write_transcript(
+    markdown: &str,
+    run_id: Option<&str>,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let exports_dir = PathBuf::from("exports");
+    fs::create_dir_all(&exports_dir).await?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let filename = match run_id {
+        Some(run_id) => format!("transcript-{}-{}.md", timestamp, run_id),
+        None => format!("transcript-{}.md", timestamp),
+    };
+    let path = exports_dir.join(filename);
+    fs::write(&path, markdown).await?;
+    Ok(path)
+}
+
+/// Appends one already-serialized `Response` line to `responses.jsonl`,
+/// building up a verbatim transcript of everything the backend sent during
+/// the run. `--replay` reads this back with `read_responses` to reconstruct
+/// the run without reconnecting to the backend.
+pub async fn append_response(run_dir: &Path, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = run_dir.join("responses.jsonl");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    file.write_all(b"\n").await?;
+    Ok(())
+}
+
+/// Reads back the `responses.jsonl` transcript written by `append_response`.
+/// Lines that fail to parse (e.g. from a transcript predating a protocol
+/// change) are skipped rather than failing the whole replay.
+pub async fn read_responses(run_dir: &Path) -> Result<Vec<Response>, Box<dyn std::error::Error>> {
+    let path = run_dir.join("responses.jsonl");
+    let content = fs::read_to_string(path).await?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
 pub async fn write_metadata(
     run_dir: &Path,
     metadata: &RunMetadata,
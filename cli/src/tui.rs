@@ -1,20 +1,38 @@
+mod browser;
+mod component;
+mod input;
 mod markdown;
+mod tokenizer;
 mod widgets;
 
+use std::collections::HashMap;
 use std::io::{self, Stdout};
+use std::path::Path;
 use std::time::Duration;
 
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseEventKind,
+};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
+use ratatui::Frame;
 use ratatui::Terminal;
 use ratatui::prelude::CrosstermBackend;
 use tokio::sync::mpsc;
 
-use crate::protocol::{ClarifyingQuestion, Response};
-use widgets::{ChatMessage, MessageRole, calculate_total_lines, render_ui};
+use crate::cli::ThemeConfig;
+use crate::protocol::{ClarifyingQuestion, Response, ToolCallStatus};
+use browser::RunSummary;
+use component::{Actions, ChatView, ClarifyingPrompt, Component, ConfirmPrompt};
+pub use input::BufferName;
+use input::InputBuffer;
+pub use markdown::DEFAULT_THEME;
+use markdown::{MarkdownRenderer, TextWrapMode};
+use tokenizer::TokenEstimator;
+use widgets::{ChatMessage, MessageRole, ToolStatus, calculate_total_lines};
 
 const SPINNER_FRAMES: [&str; 4] = ["-", "\\", "|", "/"];
 
@@ -34,52 +52,128 @@ pub struct ClarifyingState {
     pub questions: Vec<ClarifyingQuestion>,
     pub current_index: usize,
     pub answers: Vec<String>,
+    /// Question the user has jumped back to with Ctrl+Up/Down to revise its
+    /// answer, overriding `current_index` for display and for which slot in
+    /// `answers` the next submitted input overwrites. `None` means "answer
+    /// the next unanswered question", the normal forward flow.
+    pub selected_question: Option<usize>,
+}
+
+/// State for the run browser overlay opened with Ctrl+R, listing persisted
+/// runs from `runs/` so a past transcript can be reopened without re-running.
+pub struct BrowserState {
+    pub runs: Vec<RunSummary>,
+    pub selected: usize,
 }
 
 pub struct App {
     pub messages: Vec<ChatMessage>,
-    pub input: String,
+    inputs: HashMap<BufferName, InputBuffer>,
     pub status: Option<String>,
     pub is_processing: bool,
     pub scroll_offset: usize,
     pub should_quit: bool,
     pub clarifying: Option<ClarifyingState>,
     pub terminal_width: u16,
+    pub terminal_height: u16,
     pub require_confirmation: bool,
     pub pending_answers: Option<Vec<String>>,
     pub phase: AppPhase,
+    pub selected_message: Option<usize>,
+    pub selected_code_block: Option<usize>,
+    pub model: String,
+    /// Directory the tracing subsystem is writing its rolling daily log to,
+    /// surfaced in the status bar; `None` outside the TUI entrypoint.
+    pub log_path: Option<String>,
+    pub browser: Option<BrowserState>,
+    pub md_renderer: MarkdownRenderer,
     spinner_index: usize,
     event_rx: Option<mpsc::UnboundedReceiver<AppEvent>>,
     event_tx: mpsc::UnboundedSender<AppEvent>,
+    token_estimator: TokenEstimator,
+    /// Keyboard-routing and rendering stack: `ChatView` at the bottom,
+    /// plus whichever modal overlays `sync_components` currently applies.
+    components: Vec<Box<dyn Component>>,
+    /// Maps an in-progress `AppEvent::StreamBegin` message id to its index
+    /// in `messages`, so `StreamDelta`/`StreamEnd` know which message to append to.
+    streaming_messages: HashMap<String, usize>,
+    /// `run_id` of the most recently completed run, stamped into exported
+    /// transcript filenames so they can be traced back to their run.
+    last_run_id: Option<String>,
 }
 
 pub enum AppEvent {
     BackendResponse(Response),
     RunComplete { success: bool, run_id: String },
     Error(String),
+    /// A new streamed agent message has started; `message_id` identifies it
+    /// for the matching `StreamDelta`/`StreamEnd` events that follow.
+    StreamBegin { message_id: String },
+    StreamDelta { message_id: String, text: String },
+    StreamEnd { message_id: String },
+    /// A transcript export finished successfully and was written to `path`.
+    Saved { path: String },
 }
 
 impl App {
-    pub fn new(require_confirmation: bool) -> Self {
+    pub fn new(require_confirmation: bool, model: String, theme_config: ThemeConfig) -> Self {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let md_renderer = MarkdownRenderer::with_config(
+            &theme_config.theme_name,
+            theme_config.extra_syntax_dir.as_deref().map(Path::new),
+            theme_config.extra_theme_dir.as_deref().map(Path::new),
+        )
+        .with_wrap_mode(TextWrapMode::Word)
+        .with_line_numbers(theme_config.line_numbers);
         Self {
             messages: Vec::new(),
-            input: String::new(),
+            inputs: HashMap::new(),
             status: None,
             is_processing: false,
             scroll_offset: 0,
             should_quit: false,
             clarifying: None,
-            terminal_width: 80, // default, updated on each draw
+            terminal_width: 80,  // default, updated on each draw
+            terminal_height: 24, // default, updated on each draw
             require_confirmation,
             pending_answers: None,
             phase: AppPhase::Idle,
+            selected_message: None,
+            selected_code_block: None,
+            model,
+            log_path: None,
+            browser: None,
+            md_renderer,
             spinner_index: 0,
             event_rx: Some(event_rx),
             event_tx,
+            token_estimator: TokenEstimator::new(),
+            components: vec![Box::new(ChatView)],
+            streaming_messages: HashMap::new(),
+            last_run_id: None,
         }
     }
 
+    /// Estimates the token count of the accumulated transcript plus the
+    /// in-progress input, for the status-bar budget indicator.
+    pub fn estimated_tokens(&self) -> usize {
+        let transcript: usize = self
+            .messages
+            .iter()
+            .map(|msg| {
+                let cached = msg.token_count.get();
+                if let Some(count) = cached {
+                    count
+                } else {
+                    let count = self.token_estimator.count(&msg.content);
+                    msg.token_count.set(Some(count));
+                    count
+                }
+            })
+            .sum();
+        transcript + self.token_estimator.count(self.input_text())
+    }
+
     pub fn is_clarifying(&self) -> bool {
         self.clarifying.is_some()
     }
@@ -88,10 +182,66 @@ impl App {
         self.pending_answers.is_some()
     }
 
+    /// Which named buffer Enter/Backspace/Char keystrokes currently edit:
+    /// the confirm prompt, the clarifying answer in focus, or the query box.
+    pub fn active_buffer_name(&self) -> BufferName {
+        if self.awaiting_confirmation() || self.is_confirming() {
+            BufferName::Confirm
+        } else if let Some(state) = &self.clarifying {
+            BufferName::Answer(state.selected_question.unwrap_or(state.current_index))
+        } else {
+            BufferName::Query
+        }
+    }
+
+    /// Whether the active buffer accepts keystrokes right now: mid-clarification,
+    /// awaiting confirmation, or idle between runs.
+    pub fn is_editing(&self) -> bool {
+        self.is_clarifying() || self.awaiting_confirmation() || !self.is_processing
+    }
+
+    pub fn input_text(&self) -> &str {
+        self.inputs
+            .get(&self.active_buffer_name())
+            .map(InputBuffer::as_str)
+            .unwrap_or("")
+    }
+
+    pub fn input_cursor(&self) -> usize {
+        self.inputs
+            .get(&self.active_buffer_name())
+            .map(InputBuffer::cursor)
+            .unwrap_or(0)
+    }
+
+    fn input_mut(&mut self) -> &mut InputBuffer {
+        let name = self.active_buffer_name();
+        self.inputs.entry(name).or_default()
+    }
+
     pub fn current_question(&self) -> Option<&ClarifyingQuestion> {
-        self.clarifying
-            .as_ref()
-            .and_then(|state| state.questions.get(state.current_index))
+        self.clarifying.as_ref().and_then(|state| {
+            let index = state.selected_question.unwrap_or(state.current_index);
+            state.questions.get(index)
+        })
+    }
+
+    /// Moves the clarifying-question cursor by `delta`, pulling that
+    /// question's existing answer (if any) back into `input` so it can be
+    /// edited and resubmitted to overwrite `answers[index]`.
+    pub fn jump_question(&mut self, delta: isize) {
+        let Some(state) = &mut self.clarifying else {
+            return;
+        };
+        if state.questions.is_empty() {
+            return;
+        }
+        let current = state.selected_question.unwrap_or(state.current_index) as isize;
+        let next = (current + delta).clamp(0, state.questions.len() as isize - 1) as usize;
+        state.selected_question = Some(next);
+        let answer = state.answers.get(next).cloned().unwrap_or_default();
+        self.inputs
+            .insert(BufferName::Answer(next), InputBuffer::with_text(answer));
     }
 
     pub fn is_confirming(&self) -> bool {
@@ -106,6 +256,8 @@ impl App {
         self.messages.push(ChatMessage {
             role: MessageRole::User,
             content,
+            folded: false,
+            token_count: std::cell::Cell::new(None),
         });
         self.scroll_to_bottom();
     }
@@ -114,18 +266,270 @@ impl App {
         self.messages.push(ChatMessage {
             role: MessageRole::Assistant,
             content,
+            folded: false,
+            token_count: std::cell::Cell::new(None),
         });
         self.scroll_to_bottom();
     }
 
+    /// Starts a new streamed assistant message that `append_stream_delta`
+    /// calls for this `message_id` will grow token by token.
+    fn begin_stream(&mut self, message_id: String) {
+        let index = self.messages.len();
+        self.messages.push(ChatMessage {
+            role: MessageRole::Assistant,
+            content: String::new(),
+            folded: false,
+            token_count: std::cell::Cell::new(None),
+        });
+        self.streaming_messages.insert(message_id, index);
+        self.scroll_to_bottom();
+    }
+
+    /// Appends `text` to the message started by `begin_stream(message_id)`.
+    /// Only follows the transcript to the bottom if the user was already
+    /// pinned there, so scrolling up to read isn't yanked back down.
+    fn append_stream_delta(&mut self, message_id: &str, text: &str) {
+        let Some(&index) = self.streaming_messages.get(message_id) else {
+            return;
+        };
+        let was_pinned = self.scroll_offset == 0;
+        if let Some(msg) = self.messages.get_mut(index) {
+            msg.content.push_str(text);
+            msg.token_count.set(None);
+        }
+        if was_pinned {
+            self.scroll_to_bottom();
+        }
+    }
+
+    /// Marks the stream for `message_id` as finished; the message itself
+    /// stays in `messages` with whatever text it accumulated.
+    fn end_stream(&mut self, message_id: &str) {
+        self.streaming_messages.remove(message_id);
+    }
+
     pub fn add_system_message(&mut self, content: String) {
         self.messages.push(ChatMessage {
             role: MessageRole::System,
             content,
+            folded: false,
+            token_count: std::cell::Cell::new(None),
+        });
+        self.scroll_to_bottom();
+    }
+
+    fn upsert_tool_message(&mut self, name: String, args: String, status: ToolStatus, output: String) {
+        if let Some(msg) = self.messages.iter_mut().rev().find(|msg| {
+            matches!(&msg.role, MessageRole::Tool { name: n, args: a, .. } if *n == name && *a == args)
+        }) {
+            msg.role = MessageRole::Tool { name, args, status };
+            if !output.is_empty() {
+                msg.content = output;
+                msg.token_count.set(None);
+            }
+            self.scroll_to_bottom();
+            return;
+        }
+
+        self.messages.push(ChatMessage {
+            role: MessageRole::Tool { name, args, status },
+            content: output,
+            folded: true,
+            token_count: std::cell::Cell::new(None),
         });
         self.scroll_to_bottom();
     }
 
+    /// Toggles the fold state of the currently selected tool message.
+    pub fn toggle_fold_selected(&mut self) {
+        if let Some(index) = self.selected_message
+            && let Some(msg) = self.messages.get_mut(index)
+            && matches!(msg.role, MessageRole::Tool { .. })
+        {
+            msg.folded = !msg.folded;
+        }
+    }
+
+    /// Moves the message selection cursor by `delta` (negative moves up/back).
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.messages.is_empty() {
+            return;
+        }
+        let current = self.selected_message.unwrap_or(self.messages.len());
+        let next = (current as isize + delta).clamp(0, self.messages.len() as isize - 1);
+        self.selected_message = Some(next as usize);
+        self.selected_code_block = None;
+    }
+
+    /// Whether re-editing a prior message makes sense right now: idle
+    /// between runs, not mid-clarification/confirmation/research.
+    pub fn can_revise(&self) -> bool {
+        matches!(
+            self.phase,
+            AppPhase::Idle | AppPhase::Completed | AppPhase::Error
+        ) && !self.is_processing
+    }
+
+    /// Moves `selected_message` to the previous `MessageRole::User` entry,
+    /// for the "revise" flow that lets an idle/completed session re-edit and
+    /// resubmit an earlier query instead of retyping it from scratch.
+    pub fn select_previous_user_message(&mut self) {
+        self.select_user_message(-1);
+    }
+
+    /// Moves `selected_message` to the next `MessageRole::User` entry.
+    pub fn select_next_user_message(&mut self) {
+        self.select_user_message(1);
+    }
+
+    fn select_user_message(&mut self, delta: isize) {
+        let user_indices: Vec<usize> = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| matches!(msg.role, MessageRole::User))
+            .map(|(i, _)| i)
+            .collect();
+        if user_indices.is_empty() {
+            return;
+        }
+        let current_pos = self
+            .selected_message
+            .and_then(|idx| user_indices.iter().position(|&i| i == idx))
+            .unwrap_or(user_indices.len());
+        let next_pos =
+            (current_pos as isize + delta).clamp(0, user_indices.len() as isize - 1) as usize;
+        self.selected_message = Some(user_indices[next_pos]);
+    }
+
+    /// Pulls the selected message's text back into `input` for editing and
+    /// resubmission, if the selection is on a `MessageRole::User` message.
+    /// Returns whether a message was loaded.
+    pub fn edit_selected_message(&mut self) -> bool {
+        let Some(index) = self.selected_message else {
+            return false;
+        };
+        let Some(msg) = self.messages.get(index) else {
+            return false;
+        };
+        if !matches!(msg.role, MessageRole::User) {
+            return false;
+        }
+        let content = msg.content.clone();
+        self.input_mut().set(content);
+        self.selected_message = None;
+        true
+    }
+
+    /// Cycles which part of the selected assistant message is highlighted for
+    /// copying: `None` (the whole message) through each fenced code block it
+    /// contains, in document order, then back to `None`.
+    pub fn cycle_code_block(&mut self) {
+        let Some(index) = self.selected_message else {
+            return;
+        };
+        let Some(msg) = self.messages.get(index) else {
+            return;
+        };
+        if !matches!(msg.role, MessageRole::Assistant) {
+            return;
+        }
+        let block_count = markdown::extract_code_blocks(&msg.content).len();
+        if block_count == 0 {
+            return;
+        }
+        self.selected_code_block = match self.selected_code_block {
+            None => Some(0),
+            Some(i) if i + 1 < block_count => Some(i + 1),
+            Some(_) => None,
+        };
+    }
+
+    /// Returns the text the copy keybinding would place on the clipboard: the
+    /// selected code block's source, or the whole selected message otherwise.
+    pub fn selection_copy_text(&self) -> Option<String> {
+        let index = self.selected_message?;
+        let msg = self.messages.get(index)?;
+        if let (MessageRole::Assistant, Some(block_index)) = (&msg.role, self.selected_code_block) {
+            return markdown::extract_code_blocks(&msg.content)
+                .into_iter()
+                .nth(block_index);
+        }
+        Some(msg.content.clone())
+    }
+
+    /// Renders the transcript as Markdown for `Ctrl+S` export, one role
+    /// header (`User`/`Assistant`/`System`/`Tool: {name}`) per message,
+    /// writing each message's content verbatim underneath (the final
+    /// `Response::Report` is already folded into an `Assistant` message by
+    /// `handle_backend_event`, so its summary and report come along for free).
+    pub fn transcript_markdown(&self) -> String {
+        let mut out = String::new();
+        for message in &self.messages {
+            let header = match &message.role {
+                MessageRole::User => "## User".to_string(),
+                MessageRole::Assistant => "## Assistant".to_string(),
+                MessageRole::System => "## System".to_string(),
+                MessageRole::Tool { name, .. } => format!("## Tool: {}", name),
+            };
+            out.push_str(&header);
+            out.push_str("\n\n");
+            out.push_str(&message.content);
+            out.push_str("\n\n");
+        }
+        out
+    }
+
+    /// Opens the run browser overlay with the given list of persisted runs.
+    pub fn open_browser(&mut self, runs: Vec<RunSummary>) {
+        self.browser = Some(BrowserState { runs, selected: 0 });
+    }
+
+    /// Closes the run browser overlay without loading anything.
+    pub fn close_browser(&mut self) {
+        self.browser = None;
+    }
+
+    pub fn is_browsing(&self) -> bool {
+        self.browser.is_some()
+    }
+
+    /// Moves the run browser selection cursor by `delta`.
+    pub fn browser_move(&mut self, delta: isize) {
+        if let Some(state) = &mut self.browser {
+            if state.runs.is_empty() {
+                return;
+            }
+            let next = (state.selected as isize + delta).clamp(0, state.runs.len() as isize - 1);
+            state.selected = next as usize;
+        }
+    }
+
+    pub fn selected_run_id(&self) -> Option<String> {
+        self.browser
+            .as_ref()
+            .and_then(|state| state.runs.get(state.selected))
+            .map(|run| run.run_id.clone())
+    }
+
+    /// Replaces the transcript with a rehydrated run and closes the browser.
+    pub fn load_messages(&mut self, messages: Vec<ChatMessage>) {
+        self.messages = messages;
+        self.browser = None;
+        self.phase = AppPhase::Completed;
+        self.scroll_to_bottom();
+    }
+
+    /// Loads the run's transcript from its persisted artifacts, as if it had
+    /// been reopened from the Ctrl+R run browser. Used by `--replay` to open
+    /// straight into a specific run instead of starting a new one.
+    pub async fn load_persisted_run(&mut self, run_id: &str) -> io::Result<()> {
+        let messages = browser::load_run_messages(Path::new("runs"), run_id).await?;
+        self.load_messages(messages);
+        Ok(())
+    }
+
     pub fn set_status(&mut self, status: Option<String>) {
         self.status = status;
     }
@@ -164,11 +568,8 @@ impl App {
             AppPhase::AwaitingClarification => "Generating clarifying questions...".to_string(),
             AppPhase::Clarifying => {
                 if let Some(state) = &self.clarifying {
-                    format!(
-                        "Answer question {} of {}",
-                        state.current_index + 1,
-                        state.questions.len()
-                    )
+                    let index = state.selected_question.unwrap_or(state.current_index);
+                    format!("Answer question {} of {}", index + 1, state.questions.len())
                 } else {
                     "Answer the clarifying questions.".to_string()
                 }
@@ -186,6 +587,38 @@ impl App {
         self.scroll_offset = 0; // 0 = at bottom
     }
 
+    fn max_scroll(&self, width: u16) -> usize {
+        calculate_total_lines(self, width).saturating_sub(1)
+    }
+
+    /// Height of the visible chat transcript area, mirroring the layout in
+    /// `ChatView::render` (input box: 3 rows, status bar: 1 row, chat area
+    /// border: 2 rows).
+    fn chat_visible_height(&self) -> usize {
+        self.terminal_height.saturating_sub(3 + 1 + 2) as usize
+    }
+
+    /// Scrolls the transcript up (towards older messages) by `lines`.
+    pub fn scroll_up(&mut self, lines: usize, width: u16) {
+        let max_scroll = self.max_scroll(width);
+        self.scroll_offset = (self.scroll_offset + lines).min(max_scroll);
+    }
+
+    /// Scrolls the transcript down (towards newer messages) by `lines`.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(lines);
+    }
+
+    /// Jumps the transcript to the very top (the oldest message).
+    pub fn scroll_to_top(&mut self, width: u16) {
+        self.scroll_offset = self.max_scroll(width);
+    }
+
+    /// Jumps the transcript to the very bottom, matching the "0 = bottom" convention.
+    pub fn scroll_to_bottom_user(&mut self) {
+        self.scroll_to_bottom();
+    }
+
     fn handle_backend_event(&mut self, response: Response) {
         match response {
             Response::Status { message } => {
@@ -203,6 +636,7 @@ impl App {
                     questions: questions.clone(),
                     current_index: 0,
                     answers: Vec::new(),
+                    selected_question: None,
                 });
                 self.set_status(None);
                 self.phase = AppPhase::Clarifying;
@@ -222,6 +656,16 @@ impl App {
                 )));
                 self.phase = AppPhase::Researching;
             }
+            Response::ToolCall {
+                name, args, status, ..
+            } => {
+                let (tool_status, output) = match status {
+                    ToolCallStatus::Running => (ToolStatus::Running, String::new()),
+                    ToolCallStatus::Success => (ToolStatus::Success, String::new()),
+                    ToolCallStatus::Error { message } => (ToolStatus::Error(message), String::new()),
+                };
+                self.upsert_tool_message(name, args, tool_status, output);
+            }
             Response::Decision {
                 action,
                 remaining_searches,
@@ -253,7 +697,51 @@ impl App {
                 self.phase = AppPhase::Error;
                 self.add_system_message(msg);
             }
-            Response::Done { .. } | Response::Metadata { .. } => {}
+            Response::Done { .. }
+            | Response::Metadata { .. }
+            | Response::StreamBegin { .. }
+            | Response::StreamDelta { .. }
+            | Response::StreamEnd { .. } => {}
+        }
+    }
+
+    /// Renders every component on the stack, bottom (the chat view) to top
+    /// (whichever modal overlay is active), plus the run browser popup,
+    /// which sits outside the stack since loading a run is async.
+    pub fn render(&self, frame: &mut Frame) {
+        for component in &self.components {
+            component.render(frame, self);
+        }
+        if self.is_browsing() {
+            widgets::render_run_browser(frame, self, frame.area());
+        }
+    }
+
+    /// Offers `key` to the component stack top-down, stopping at the first
+    /// one that reports `EventResult::Consumed`. Temporarily takes the
+    /// stack out of `self` so each component can mutate `App` freely
+    /// without also holding a borrow of `self.components`.
+    pub fn dispatch_key(&mut self, key: KeyEvent, actions: &mut Actions) {
+        let mut components = std::mem::take(&mut self.components);
+        for component in components.iter_mut().rev() {
+            if component.handle_key(self, key, actions) == component::EventResult::Consumed {
+                break;
+            }
+        }
+        self.components = components;
+    }
+
+    /// Rebuilds the overlay stack from current phase/state, on top of the
+    /// ever-present `ChatView`. Called after anything that might change
+    /// `clarifying`/`pending_answers`/`phase`, so a new modal surface only
+    /// needs a predicate and a push here, not a call at every transition site.
+    fn sync_components(&mut self) {
+        self.components.truncate(1);
+        if self.is_clarifying() {
+            self.components.push(Box::new(ClarifyingPrompt));
+        }
+        if self.awaiting_confirmation() || self.is_confirming() {
+            self.components.push(Box::new(ConfirmPrompt));
         }
     }
 
@@ -287,6 +775,7 @@ impl App {
                     } else {
                         self.add_system_message("Research failed".to_string());
                     }
+                    self.last_run_id = Some(run_id);
                 }
                 AppEvent::Error(msg) => {
                     self.is_processing = false;
@@ -294,6 +783,18 @@ impl App {
                     self.phase = AppPhase::Error;
                     self.add_system_message(format!("Error: {}", msg));
                 }
+                AppEvent::StreamBegin { message_id } => {
+                    self.begin_stream(message_id);
+                }
+                AppEvent::StreamDelta { message_id, text } => {
+                    self.append_stream_delta(&message_id, &text);
+                }
+                AppEvent::StreamEnd { message_id } => {
+                    self.end_stream(&message_id);
+                }
+                AppEvent::Saved { path } => {
+                    self.add_system_message(format!("Saved transcript to {}", path));
+                }
             }
         }
     }
@@ -307,7 +808,7 @@ impl Tui {
     pub fn new() -> io::Result<Self> {
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
         Ok(Self { terminal })
@@ -315,190 +816,103 @@ impl Tui {
 
     pub fn restore(&mut self) -> io::Result<()> {
         disable_raw_mode()?;
-        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
+        execute!(
+            self.terminal.backend_mut(),
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        )?;
         Ok(())
     }
 
-    pub async fn run<F, G, H>(
+    pub async fn run<F, G, H, I>(
         &mut self,
         app: &mut App,
         mut on_submit: F,
         mut on_answers: G,
         mut on_interrupt: H,
+        mut on_save: I,
     ) -> io::Result<()>
     where
         F: FnMut(&str) + Send,
         G: FnMut(Vec<String>, bool) + Send,
         H: FnMut() + Send,
+        I: FnMut(String, Option<String>) + Send,
     {
         loop {
             app.process_events();
+            app.sync_components();
 
             let size = self.terminal.size()?;
             app.terminal_width = size.width;
+            app.terminal_height = size.height;
 
             self.terminal.draw(|frame| {
-                render_ui(frame, app);
+                app.render(frame);
             })?;
 
             if app.should_quit {
                 break;
             }
 
-            if event::poll(Duration::from_millis(50))?
-                && let Event::Key(key) = event::read()?
-            {
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
+            if !event::poll(Duration::from_millis(50))? {
+                continue;
+            }
 
-                match key.code {
-                    KeyCode::Esc => {
-                        if app.is_processing {
-                            on_interrupt();
-                            app.add_system_message("Stopping research...".to_string());
-                            app.phase = AppPhase::Researching;
-                        } else {
-                            app.should_quit = true;
-                        }
+            match event::read()? {
+                Event::Mouse(mouse) => {
+                    let step = if mouse.modifiers.contains(KeyModifiers::SHIFT) {
+                        5
+                    } else {
+                        3
+                    };
+                    match mouse.kind {
+                        MouseEventKind::ScrollUp => app.scroll_up(step, app.terminal_width),
+                        MouseEventKind::ScrollDown => app.scroll_down(step),
+                        _ => {}
                     }
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if app.is_processing {
-                            on_interrupt();
-                            app.add_system_message("Stopping research...".to_string());
-                            app.phase = AppPhase::Researching;
-                        }
-                        app.should_quit = true;
+                }
+                Event::Key(key) => {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
                     }
-                    KeyCode::Enter => {
-                        if app.awaiting_confirmation() {
-                            let user_input = app.input.trim().to_string();
-                            let lowered = user_input.to_lowercase();
-                            app.input.clear();
-
-                            if !user_input.is_empty() {
-                                app.add_user_message(user_input.clone());
-                            }
-
-                            let confirmed = matches!(
-                                lowered.as_str(),
-                                "" | "y" | "yes" | "confirm" | "continue" | "proceed"
-                            );
-                            let cancelled =
-                                matches!(lowered.as_str(), "n" | "no" | "cancel" | "stop" | "quit");
-
-                            if confirmed {
-                                if let Some(answers) = app.pending_answers.take() {
-                                    app.set_status(Some("Continuing research...".to_string()));
-                                    app.phase = AppPhase::Researching;
-                                    on_answers(answers, true);
-                                }
-                            } else if cancelled {
-                                if let Some(answers) = app.pending_answers.take() {
-                                    app.set_status(Some("Cancelling research...".to_string()));
-                                    app.add_system_message(
-                                        "Research cancelled before execution.".to_string(),
-                                    );
-                                    app.phase = AppPhase::Completed;
-                                    on_answers(answers, false);
-                                }
-                            } else {
-                                app.add_system_message(
-                                    "Type 'confirm' to continue or 'cancel' to abort.".to_string(),
-                                );
-                            }
-                            continue;
-                        }
 
-                        if app.is_clarifying() {
-                            let answer = app.input.clone();
-                            app.input.clear();
-                            app.add_user_message(answer.clone());
-
-                            let (is_complete, answers, next_index, total) = {
-                                let state = app.clarifying.as_mut().unwrap();
-                                state.answers.push(answer);
-                                state.current_index += 1;
-                                let complete = state.current_index >= state.questions.len();
-                                let answers = if complete {
-                                    Some(state.answers.clone())
-                                } else {
-                                    None
-                                };
-                                (
-                                    complete,
-                                    answers,
-                                    state.current_index,
-                                    state.questions.len(),
-                                )
-                            };
-
-                            if is_complete {
-                                app.clarifying = None;
-                                if app.require_confirmation {
-                                    if let Some(answers) = answers {
-                                        app.pending_answers = Some(answers);
-                                        app.add_system_message(
-                                            "Type 'confirm' to continue or 'cancel' to abort."
-                                                .to_string(),
-                                        );
-                                        app.set_status(Some(
-                                            "Awaiting confirmation...".to_string(),
-                                        ));
-                                        app.phase = AppPhase::Confirming;
-                                    }
-                                } else {
-                                    app.set_status(Some("Continuing research...".to_string()));
-                                    app.phase = AppPhase::Researching;
-                                    if let Some(answers) = answers {
-                                        on_answers(answers, true);
-                                    }
+                    if app.is_browsing() {
+                        match key.code {
+                            KeyCode::Up => app.browser_move(-1),
+                            KeyCode::Down => app.browser_move(1),
+                            KeyCode::Esc => app.close_browser(),
+                            KeyCode::Enter => {
+                                if let Some(run_id) = app.selected_run_id()
+                                    && let Ok(messages) =
+                                        browser::load_run_messages(Path::new("runs"), &run_id)
+                                            .await
+                                {
+                                    app.load_messages(messages);
                                 }
-                            } else {
-                                app.set_status(Some(format!(
-                                    "Answer question {} of {}",
-                                    next_index + 1,
-                                    total
-                                )));
-                                app.phase = AppPhase::Clarifying;
                             }
-                        } else if !app.input.is_empty() && !app.is_processing {
-                            let query = app.input.clone();
-                            app.input.clear();
-                            app.add_user_message(query.clone());
-                            app.is_processing = true;
-                            app.set_status(Some("Starting research...".to_string()));
-                            app.phase = AppPhase::AwaitingClarification;
-                            on_submit(&query);
+                            _ => {}
                         }
+                        continue;
                     }
-                    KeyCode::Backspace => {
-                        if app.is_clarifying() || app.awaiting_confirmation() || !app.is_processing
-                        {
-                            app.input.pop();
-                        }
-                    }
-                    KeyCode::Char(c) => {
-                        if app.is_clarifying() || app.awaiting_confirmation() || !app.is_processing
-                        {
-                            app.input.push(c);
-                        }
-                    }
-                    KeyCode::Up => {
-                        let total_lines = calculate_total_lines(app, app.terminal_width);
-                        let max_scroll = total_lines.saturating_sub(1);
-                        if app.scroll_offset < max_scroll {
-                            app.scroll_offset += 3;
-                            app.scroll_offset = app.scroll_offset.min(max_scroll);
-                        }
-                    }
-                    KeyCode::Down => {
-                        if app.scroll_offset > 0 {
-                            app.scroll_offset = app.scroll_offset.saturating_sub(3);
+
+                    if key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        if let Ok(runs) = browser::list_runs(Path::new("runs")).await {
+                            app.open_browser(runs);
                         }
+                        continue;
                     }
-                    _ => {}
+
+                    let mut actions = Actions {
+                        on_submit: &mut on_submit,
+                        on_answers: &mut on_answers,
+                        on_interrupt: &mut on_interrupt,
+                        on_save: &mut on_save,
+                    };
+                    app.dispatch_key(key, &mut actions);
+                    app.sync_components();
                 }
+                _ => {}
             }
         }
 
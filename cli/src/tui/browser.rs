@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use tokio::fs;
+
+use super::widgets::{ChatMessage, MessageRole, ToolStatus};
+use crate::protocol::ToolCallStatus;
+
+/// Summary of a persisted run shown in the run browser list, read from its
+/// `metadata.json` without touching `prompts/`/`raw_responses/`.
+#[derive(Clone)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub model: Option<String>,
+    pub duration_ms: u64,
+    pub total_tokens: Option<u32>,
+    pub trace_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StoredMetadata {
+    run_id: String,
+    model: Option<String>,
+    total_tokens: Option<u32>,
+    duration_ms: u64,
+    trace_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StoredRequest {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct StoredResponse {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct StoredToolCall {
+    name: String,
+    args: String,
+    #[serde(flatten)]
+    status: ToolCallStatus,
+}
+
+/// Lists the runs persisted under `runs_dir`, most recently modified first.
+pub async fn list_runs(runs_dir: &Path) -> std::io::Result<Vec<RunSummary>> {
+    let mut dir = match fs::read_dir(runs_dir).await {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut runs: Vec<(std::time::SystemTime, RunSummary)> = Vec::new();
+    while let Some(entry) = dir.next_entry().await? {
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+        let Ok(raw) = fs::read_to_string(entry.path().join("metadata.json")).await else {
+            continue;
+        };
+        let Ok(metadata) = serde_json::from_str::<StoredMetadata>(&raw) else {
+            continue;
+        };
+        let modified = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+
+        runs.push((
+            modified,
+            RunSummary {
+                run_id: metadata.run_id,
+                model: metadata.model,
+                duration_ms: metadata.duration_ms,
+                total_tokens: metadata.total_tokens,
+                trace_url: metadata.trace_url,
+            },
+        ));
+    }
+
+    runs.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(runs.into_iter().map(|(_, summary)| summary).collect())
+}
+
+/// Rehydrates a completed run's `prompts/`, `raw_responses/`, and `output.md`
+/// into the chat transcript shown by `render_chat_area`, in sequence order.
+pub async fn load_run_messages(runs_dir: &Path, run_id: &str) -> std::io::Result<Vec<ChatMessage>> {
+    let run_dir = runs_dir.join(run_id);
+    let mut messages = Vec::new();
+
+    if let Ok(raw) = fs::read_to_string(run_dir.join("request.json")).await
+        && let Ok(request) = serde_json::from_str::<StoredRequest>(&raw)
+    {
+        messages.push(chat_message(MessageRole::User, request.query));
+    }
+
+    // (sequence, sub-order within the sequence, message) so prompts render
+    // before the tool calls and responses they led to.
+    let mut entries: Vec<(u32, u8, ChatMessage)> = Vec::new();
+
+    if let Ok(mut dir) = fs::read_dir(run_dir.join("prompts")).await {
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some((sequence, agent)) = parse_filename(&name) else {
+                continue;
+            };
+            entries.push((
+                sequence,
+                0,
+                chat_message(
+                    MessageRole::System,
+                    format!("Prompt → {} (step {})", agent, sequence),
+                ),
+            ));
+        }
+    }
+
+    if let Ok(mut dir) = fs::read_dir(run_dir.join("raw_responses")).await {
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some((sequence, _)) = parse_filename(&name) else {
+                continue;
+            };
+            let Ok(raw) = fs::read_to_string(entry.path()).await else {
+                continue;
+            };
+
+            if name.contains("-tool-") {
+                if let Ok(tool) = serde_json::from_str::<StoredToolCall>(&raw) {
+                    let status = match tool.status {
+                        ToolCallStatus::Running => ToolStatus::Running,
+                        ToolCallStatus::Success => ToolStatus::Success,
+                        ToolCallStatus::Error { message } => ToolStatus::Error(message),
+                    };
+                    entries.push((
+                        sequence,
+                        1,
+                        ChatMessage {
+                            role: MessageRole::Tool {
+                                name: tool.name,
+                                args: tool.args,
+                                status,
+                            },
+                            content: String::new(),
+                            folded: true,
+                            token_count: std::cell::Cell::new(None),
+                        },
+                    ));
+                }
+            } else if let Ok(response) = serde_json::from_str::<StoredResponse>(&raw) {
+                entries.push((sequence, 2, chat_message(MessageRole::Assistant, response.content)));
+            }
+        }
+    }
+
+    entries.sort_by_key(|(sequence, order, _)| (*sequence, *order));
+    messages.extend(entries.into_iter().map(|(_, _, message)| message));
+
+    if let Ok(report) = fs::read_to_string(run_dir.join("output.md")).await {
+        messages.push(chat_message(MessageRole::Assistant, report));
+    }
+
+    Ok(messages)
+}
+
+fn chat_message(role: MessageRole, content: String) -> ChatMessage {
+    ChatMessage {
+        role,
+        content,
+        folded: false,
+        token_count: std::cell::Cell::new(None),
+    }
+}
+
+/// Parses `"{sequence:03}-{agent}.ext"` / `"{sequence:03}-{agent}-tool-{name}.json"`
+/// filenames written by `run::write_prompt`/`write_raw_response`/`write_tool_call`.
+fn parse_filename(name: &str) -> Option<(u32, String)> {
+    let (sequence_str, rest) = name.split_once('-')?;
+    let sequence = sequence_str.parse().ok()?;
+    let agent = rest.split('.').next().unwrap_or(rest);
+    let agent = agent.split("-tool-").next().unwrap_or(agent);
+    Some((sequence, agent.to_string()))
+}
@@ -0,0 +1,412 @@
+//! Modal overlay / component-dispatch layer for `Tui::run`'s keyboard
+//! routing.
+//!
+//! `App` keeps a stack of `Component`s in `App::components`. Each key press
+//! is offered to the top of the stack first via `handle_key`; a component
+//! returns `EventResult::Consumed` to stop it there, or `EventResult::Ignored`
+//! to let the key fall through to whatever sits beneath it. `ChatView` (the
+//! transcript, query box, and status bar) is always at the bottom of the
+//! stack; `ClarifyingPrompt` and `ConfirmPrompt` are synced on top of it for
+//! as long as `App` is in that phase (see `App::sync_components`). A new
+//! modal surface — a `/help` screen, a full-screen report reader — can be
+//! added by implementing `Component` and listing it there, without touching
+//! this dispatch loop.
+
+use arboard::Clipboard;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+
+use super::widgets;
+use super::{App, AppPhase};
+
+#[derive(PartialEq, Eq)]
+pub enum EventResult {
+    Consumed,
+    Ignored,
+}
+
+/// Backend-triggering callbacks threaded down to whichever component
+/// handles a key, kept separate from `App` so keyboard routing doesn't need
+/// to know how a query is actually submitted, answered, or interrupted.
+pub struct Actions<'a> {
+    pub on_submit: &'a mut dyn FnMut(&str),
+    pub on_answers: &'a mut dyn FnMut(Vec<String>, bool),
+    pub on_interrupt: &'a mut dyn FnMut(),
+    pub on_save: &'a mut dyn FnMut(String, Option<String>),
+}
+
+/// A self-contained keyboard-handling and rendering surface on `App`'s
+/// component stack.
+pub trait Component {
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent, actions: &mut Actions) -> EventResult;
+    fn render(&self, frame: &mut Frame, app: &App);
+}
+
+/// The always-present base view: transcript, query/input box, and status
+/// bar. Handles everything not claimed by a modal overlay above it.
+pub struct ChatView;
+
+impl Component for ChatView {
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent, actions: &mut Actions) -> EventResult {
+        match key.code {
+            KeyCode::Esc => {
+                if app.is_processing {
+                    (actions.on_interrupt)();
+                    app.add_system_message("Stopping research...".to_string());
+                    app.phase = AppPhase::Researching;
+                } else {
+                    app.should_quit = true;
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.toggle_fold_selected();
+                EventResult::Consumed
+            }
+            KeyCode::Tab => {
+                app.cycle_code_block();
+                EventResult::Consumed
+            }
+            KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(text) = app.selection_copy_text() {
+                    match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+                        Ok(()) => app.set_status(Some("Copied to clipboard".to_string())),
+                        Err(e) => app.set_status(Some(format!("Copy failed: {}", e))),
+                    }
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::ALT) => {
+                app.move_selection(-1);
+                EventResult::Consumed
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::ALT) => {
+                app.move_selection(1);
+                EventResult::Consumed
+            }
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if app.can_revise() {
+                    app.select_previous_user_message();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if app.can_revise() {
+                    app.select_next_user_message();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if app.is_processing {
+                    (actions.on_interrupt)();
+                    app.add_system_message("Stopping research...".to_string());
+                    app.phase = AppPhase::Researching;
+                }
+                app.should_quit = true;
+                EventResult::Consumed
+            }
+            KeyCode::Enter => {
+                if app.selected_message.is_some() && app.can_revise() {
+                    if app.edit_selected_message() {
+                        app.set_status(Some(
+                            "Editing previous message, press Enter to resubmit".to_string(),
+                        ));
+                    }
+                } else if !app.input_text().is_empty() && !app.is_processing {
+                    let query = app.input_text().to_string();
+                    app.input_mut().clear();
+                    app.add_user_message(query.clone());
+                    app.is_processing = true;
+                    app.set_status(Some("Starting research...".to_string()));
+                    app.phase = AppPhase::AwaitingClarification;
+                    (actions.on_submit)(&query);
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let markdown = app.transcript_markdown();
+                let run_id = app.last_run_id.clone();
+                (actions.on_save)(markdown, run_id);
+                app.set_status(Some("Saving transcript...".to_string()));
+                EventResult::Consumed
+            }
+            KeyCode::Backspace
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    || key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                if app.is_editing() {
+                    app.input_mut().delete_word_backward();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Backspace => {
+                if app.is_editing() {
+                    app.input_mut().delete_backward();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Char(c) => {
+                if app.is_editing() {
+                    app.input_mut().insert_char(c);
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Left => {
+                if app.is_editing() {
+                    app.input_mut().move_left();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Right => {
+                if app.is_editing() {
+                    app.input_mut().move_right();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Home if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.scroll_to_top(app.terminal_width);
+                EventResult::Consumed
+            }
+            KeyCode::End if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.scroll_to_bottom_user();
+                EventResult::Consumed
+            }
+            KeyCode::Home => {
+                if app.is_editing() {
+                    app.input_mut().move_home();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::End => {
+                if app.is_editing() {
+                    app.input_mut().move_end();
+                }
+                EventResult::Consumed
+            }
+            KeyCode::PageUp => {
+                let step = app.chat_visible_height().max(1);
+                app.scroll_up(step, app.terminal_width);
+                EventResult::Consumed
+            }
+            KeyCode::PageDown => {
+                let step = app.chat_visible_height().max(1);
+                app.scroll_down(step);
+                EventResult::Consumed
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+
+    fn render(&self, frame: &mut Frame, app: &App) {
+        let chunks = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .split(frame.area());
+
+        widgets::render_chat_area(frame, app, chunks[0]);
+        widgets::render_input(frame, app, chunks[1]);
+        widgets::render_status_bar(frame, app, chunks[2]);
+    }
+}
+
+/// Modal overlay active while `App` is collecting clarifying answers;
+/// claims the keys that would otherwise edit or submit the query box.
+pub struct ClarifyingPrompt;
+
+impl Component for ClarifyingPrompt {
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent, actions: &mut Actions) -> EventResult {
+        match key.code {
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.jump_question(-1);
+                EventResult::Consumed
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.jump_question(1);
+                EventResult::Consumed
+            }
+            KeyCode::Enter => {
+                let answer = app.input_text().to_string();
+                app.input_mut().clear();
+                app.add_user_message(answer.clone());
+
+                let (is_complete, answers, next_index, total) = {
+                    let state = app.clarifying.as_mut().unwrap();
+                    if let Some(revise_index) = state.selected_question.take() {
+                        if revise_index < state.answers.len() {
+                            state.answers[revise_index] = answer;
+                        } else {
+                            while state.answers.len() < revise_index {
+                                state.answers.push(String::new());
+                            }
+                            state.answers.push(answer);
+                        }
+                        state.current_index = state.current_index.max(revise_index + 1);
+                    } else {
+                        state.answers.push(answer);
+                        state.current_index += 1;
+                    }
+                    let complete = state.current_index >= state.questions.len();
+                    let answers = if complete {
+                        Some(state.answers.clone())
+                    } else {
+                        None
+                    };
+                    (
+                        complete,
+                        answers,
+                        state.current_index,
+                        state.questions.len(),
+                    )
+                };
+
+                if is_complete {
+                    app.clarifying = None;
+                    if app.require_confirmation {
+                        if let Some(answers) = answers {
+                            app.pending_answers = Some(answers);
+                            app.add_system_message(
+                                "Type 'confirm' to continue or 'cancel' to abort.".to_string(),
+                            );
+                            app.set_status(Some("Awaiting confirmation...".to_string()));
+                            app.phase = AppPhase::Confirming;
+                        }
+                    } else {
+                        app.set_status(Some("Continuing research...".to_string()));
+                        app.phase = AppPhase::Researching;
+                        if let Some(answers) = answers {
+                            (actions.on_answers)(answers, true);
+                        }
+                    }
+                } else {
+                    app.set_status(Some(format!(
+                        "Answer question {} of {}",
+                        next_index + 1,
+                        total
+                    )));
+                    app.phase = AppPhase::Clarifying;
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Backspace
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    || key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                app.input_mut().delete_word_backward();
+                EventResult::Consumed
+            }
+            KeyCode::Backspace => {
+                app.input_mut().delete_backward();
+                EventResult::Consumed
+            }
+            KeyCode::Char(c) => {
+                app.input_mut().insert_char(c);
+                EventResult::Consumed
+            }
+            KeyCode::Left => {
+                app.input_mut().move_left();
+                EventResult::Consumed
+            }
+            KeyCode::Right => {
+                app.input_mut().move_right();
+                EventResult::Consumed
+            }
+            KeyCode::Home => {
+                app.input_mut().move_home();
+                EventResult::Consumed
+            }
+            KeyCode::End => {
+                app.input_mut().move_end();
+                EventResult::Consumed
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+
+    fn render(&self, _frame: &mut Frame, _app: &App) {}
+}
+
+/// Modal overlay active while `App` is awaiting (or showing) the
+/// confirm/cancel prompt before a research run proceeds.
+pub struct ConfirmPrompt;
+
+impl Component for ConfirmPrompt {
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent, actions: &mut Actions) -> EventResult {
+        match key.code {
+            KeyCode::Enter => {
+                let user_input = app.input_text().trim().to_string();
+                let lowered = user_input.to_lowercase();
+                app.input_mut().clear();
+
+                if !user_input.is_empty() {
+                    app.add_user_message(user_input.clone());
+                }
+
+                let confirmed = matches!(
+                    lowered.as_str(),
+                    "" | "y" | "yes" | "confirm" | "continue" | "proceed"
+                );
+                let cancelled =
+                    matches!(lowered.as_str(), "n" | "no" | "cancel" | "stop" | "quit");
+
+                if confirmed {
+                    if let Some(answers) = app.pending_answers.take() {
+                        app.set_status(Some("Continuing research...".to_string()));
+                        app.phase = AppPhase::Researching;
+                        (actions.on_answers)(answers, true);
+                    }
+                } else if cancelled {
+                    if let Some(answers) = app.pending_answers.take() {
+                        app.set_status(Some("Cancelling research...".to_string()));
+                        app.add_system_message(
+                            "Research cancelled before execution.".to_string(),
+                        );
+                        app.phase = AppPhase::Completed;
+                        (actions.on_answers)(answers, false);
+                    }
+                } else {
+                    app.add_system_message(
+                        "Type 'confirm' to continue or 'cancel' to abort.".to_string(),
+                    );
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Backspace
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    || key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                app.input_mut().delete_word_backward();
+                EventResult::Consumed
+            }
+            KeyCode::Backspace => {
+                app.input_mut().delete_backward();
+                EventResult::Consumed
+            }
+            KeyCode::Char(c) => {
+                app.input_mut().insert_char(c);
+                EventResult::Consumed
+            }
+            KeyCode::Left => {
+                app.input_mut().move_left();
+                EventResult::Consumed
+            }
+            KeyCode::Right => {
+                app.input_mut().move_right();
+                EventResult::Consumed
+            }
+            KeyCode::Home => {
+                app.input_mut().move_home();
+                EventResult::Consumed
+            }
+            KeyCode::End => {
+                app.input_mut().move_end();
+                EventResult::Consumed
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+
+    fn render(&self, _frame: &mut Frame, _app: &App) {}
+}
@@ -0,0 +1,125 @@
+//! Cursor-based editable text buffers for the TUI.
+//!
+//! Replaces the old append-only `input: String` with an `InputBuffer` that
+//! tracks a cursor position, so the query box, each clarifying-answer slot,
+//! and the confirm prompt can each be edited in place rather than only at
+//! the end. The cursor is tracked in `char` units, matching the char-count
+//! convention the rest of the TUI already uses for render-width math
+//! (see `widgets::wrapped_line_count`).
+
+use std::collections::HashMap;
+
+/// Identifies which editable field an `InputBuffer` belongs to, so distinct
+/// fields keep independent text and cursor state in `App`'s buffer map.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferName {
+    Query,
+    /// A clarifying answer, keyed by its question index so revising an
+    /// earlier answer doesn't disturb the one being typed now.
+    Answer(usize),
+    Confirm,
+}
+
+/// Map of named input buffers backing `App`'s editable fields.
+pub type InputBufferMap = HashMap<BufferName, InputBuffer>;
+
+/// A single editable text field with a cursor position.
+#[derive(Clone, Default)]
+pub struct InputBuffer {
+    text: String,
+    /// Cursor position in chars, in `0..=self.text.chars().count()`.
+    cursor: usize,
+}
+
+impl InputBuffer {
+    pub fn with_text(text: String) -> Self {
+        let cursor = text.chars().count();
+        Self { text, cursor }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replaces the buffer's text and moves the cursor to the end.
+    pub fn set(&mut self, text: String) {
+        self.cursor = text.chars().count();
+        self.text = text;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+
+    /// Inserts `c` at the cursor and advances the cursor past it.
+    pub fn insert_char(&mut self, c: char) {
+        let at = self.byte_index(self.cursor);
+        self.text.insert(at, c);
+        self.cursor += 1;
+    }
+
+    /// Deletes the char immediately before the cursor, like Backspace.
+    pub fn delete_backward(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        let end = self.byte_index(self.cursor);
+        let start = self.byte_index(self.cursor - 1);
+        self.text.replace_range(start..end, "");
+        self.cursor -= 1;
+    }
+
+    /// Deletes back to the start of the previous word, like Alt/Ctrl+Backspace
+    /// in most terminal line editors.
+    pub fn delete_word_backward(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut start = self.cursor;
+        while start > 0 && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        let end_byte = self.byte_index(self.cursor);
+        let start_byte = self.byte_index(start);
+        self.text.replace_range(start_byte..end_byte, "");
+        self.cursor = start;
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        let len = self.text.chars().count();
+        self.cursor = (self.cursor + 1).min(len);
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+}
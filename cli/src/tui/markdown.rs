@@ -1,432 +1,987 @@
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+
+use pulldown_cmark::{
+    Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd,
+};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style as SyntectStyle, ThemeSet};
+use syntect::highlighting::{FontStyle, Style as SyntectStyle, ThemeSet};
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
 
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+}
+
+/// How many colors the target terminal can render, used to downsample
+/// syntect's truecolor highlight output so it doesn't look washed out (or
+/// wrong) on terminals without 24-bit color support.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Detects the depth from `$COLORTERM`/`$TERM`, the same signals most
+    /// terminal syntax highlighters use. Callers with a more reliable signal
+    /// (a config flag, a capability probe) can skip this via
+    /// `MarkdownRenderer::with_color_depth`.
+    pub fn detect() -> Self {
+        if let Ok(colorterm) = std::env::var("COLORTERM")
+            && (colorterm.contains("truecolor") || colorterm.contains("24bit"))
+        {
+            return ColorDepth::TrueColor;
+        }
+
+        match std::env::var("TERM") {
+            Ok(term) if term.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// The theme used when a caller doesn't ask for a specific one, or asks for
+/// one that doesn't exist in the loaded `ThemeSet`.
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// How a code block's source lines are wrapped to fit a target render width,
+/// used by `MarkdownRenderer::render_wrapped`. `NoWrap` keeps `render`'s
+/// current behavior of one `Line` per source line, left for the caller's
+/// own (ratatui `Paragraph`) wrapping to truncate or wrap as best it can.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextWrapMode {
+    #[default]
+    NoWrap,
+    Char,
+    Word,
+}
+
 pub struct MarkdownRenderer {
     syntax_set: SyntaxSet,
     theme_set: ThemeSet,
+    theme_name: String,
+    color_depth: ColorDepth,
+    wrap_mode: TextWrapMode,
+    show_line_numbers: bool,
 }
 
 impl MarkdownRenderer {
     pub fn new() -> Self {
+        Self::with_config(DEFAULT_THEME, None, None)
+    }
+
+    /// Builds a renderer with a user-selected theme and, optionally, extra
+    /// `.sublime-syntax`/`.tmTheme` assets loaded from disk on top of
+    /// syntect's bundled defaults — e.g. a user config directory pointed to
+    /// by a CLI flag.
+    ///
+    /// Falls back to the bundled defaults whenever a requested asset is
+    /// missing or fails to load, rather than failing renderer construction
+    /// over one bad theme name or directory.
+    pub fn with_config(
+        theme_name: &str,
+        extra_syntax_dir: Option<&Path>,
+        extra_theme_dir: Option<&Path>,
+    ) -> Self {
+        let syntax_set = match extra_syntax_dir {
+            Some(dir) => {
+                let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+                if let Err(err) = builder.add_from_folder(dir, true) {
+                    tracing::warn!(dir = %dir.display(), %err, "failed to load extra syntax definitions");
+                }
+                builder.build()
+            }
+            None => SyntaxSet::load_defaults_newlines(),
+        };
+
+        let mut theme_set = ThemeSet::load_defaults();
+        if let Some(dir) = extra_theme_dir
+            && let Err(err) = theme_set.add_from_folder(dir)
+        {
+            tracing::warn!(dir = %dir.display(), %err, "failed to load extra themes");
+        }
+
         Self {
-            syntax_set: SyntaxSet::load_defaults_newlines(),
-            theme_set: ThemeSet::load_defaults(),
+            theme_name: resolve_theme_name(&theme_set, theme_name),
+            syntax_set,
+            theme_set,
+            color_depth: ColorDepth::detect(),
+            wrap_mode: TextWrapMode::default(),
+            show_line_numbers: false,
         }
     }
 
+    /// Loads a precompiled, zlib-compressed `bincode` dump of a `SyntaxSet`
+    /// and `ThemeSet` — produced offline and bundled as a binary asset — so
+    /// startup doesn't pay the `load_defaults_newlines()`/`load_defaults()`
+    /// parse cost on every run. Returns `None` (after logging a warning) on
+    /// any I/O or decode error, leaving the caller to fall back to
+    /// `new`/`with_config`.
+    pub fn from_packed_assets(path: &Path, theme_name: &str) -> Option<Self> {
+        match load_packed_assets(path) {
+            Ok((syntax_set, theme_set)) => Some(Self {
+                theme_name: resolve_theme_name(&theme_set, theme_name),
+                syntax_set,
+                theme_set,
+                color_depth: ColorDepth::detect(),
+                wrap_mode: TextWrapMode::default(),
+                show_line_numbers: false,
+            }),
+            Err(err) => {
+                tracing::warn!(path = %path.display(), %err, "failed to load packed syntax/theme bundle");
+                None
+            }
+        }
+    }
+
+    /// Overrides the auto-detected color depth, e.g. from a config setting.
+    pub fn with_color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.color_depth = color_depth;
+        self
+    }
+
+    /// Sets how code-block lines wrap when rendered through `render_wrapped`.
+    /// Has no effect on `render`/`render_with_block_ranges`, which always
+    /// emit one `Line` per source line.
+    pub fn with_wrap_mode(mut self, wrap_mode: TextWrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    /// Enables a right-aligned line-number gutter on code blocks, through
+    /// both `render` and `render_wrapped`.
+    pub fn with_line_numbers(mut self, show_line_numbers: bool) -> Self {
+        self.show_line_numbers = show_line_numbers;
+        self
+    }
+
     pub fn render<'a>(&self, markdown: &'a str) -> Text<'a> {
-        let mut lines: Vec<Line> = Vec::new();
-        let mut in_code_block = false;
-        let mut code_lang: Option<String> = None;
-        let mut code_buffer: Vec<&str> = Vec::new();
-
-        for line in markdown.lines() {
-            if line.starts_with("```") {
-                if in_code_block {
-                    // End code block - render accumulated code
-                    let code = code_buffer.join("\n");
-                    let highlighted = self.highlight_code(&code, code_lang.as_deref());
-                    lines.extend(highlighted);
-                    code_buffer.clear();
-                    code_lang = None;
-                    in_code_block = false;
+        self.render_with_block_ranges(markdown).0
+    }
+
+    /// Same as `render`, but wraps code-block lines to fit `width` columns
+    /// (per `self.wrap_mode`) and, if `self.show_line_numbers` is set, adds a
+    /// line-number gutter — so long code samples stay readable in a narrow
+    /// pane instead of overflowing and getting hard-truncated by the caller.
+    pub fn render_wrapped<'a>(&self, markdown: &'a str, width: usize) -> Text<'a> {
+        self.render_wrapped_with_block_ranges(markdown, width).0
+    }
+
+    /// Same as `render_wrapped`, but also returns each code block's line
+    /// range, as `render_with_block_ranges` does for the unwrapped path.
+    pub fn render_wrapped_with_block_ranges<'a>(
+        &self,
+        markdown: &'a str,
+        width: usize,
+    ) -> (Text<'a>, Vec<Range<usize>>) {
+        let parser = Parser::new_ext(markdown, parser_options());
+        let mut builder = Builder::new(self);
+        builder.wrap_width = Some(width);
+        for event in parser {
+            builder.handle_event(event);
+        }
+        builder.finish()
+    }
+
+    /// Same as `render`, but also returns the line-index range each fenced
+    /// code block occupies in the output (header/body/footer inclusive), so
+    /// the transcript can highlight a single selected block for copying.
+    ///
+    /// Parses `markdown` into a real CommonMark event stream (tables, nested
+    /// emphasis, task lists, footnotes, blockquote/list nesting) rather than
+    /// scanning it line by line, then lowers that stream into `Line`/`Span`
+    /// values one block at a time.
+    pub fn render_with_block_ranges<'a>(
+        &self,
+        markdown: &'a str,
+    ) -> (Text<'a>, Vec<Range<usize>>) {
+        let parser = Parser::new_ext(markdown, parser_options());
+        let mut builder = Builder::new(self);
+        for event in parser {
+            builder.handle_event(event);
+        }
+        builder.finish()
+    }
+
+    fn highlight_code(
+        &self,
+        code: &str,
+        lang: Option<&str>,
+        wrap_width: Option<usize>,
+    ) -> Vec<Line<'static>> {
+        let syntax = lang
+            .and_then(|l| self.syntax_set.find_syntax_by_token(l))
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+
+        let theme = &self.theme_set.themes[&self.theme_name];
+        let default_bg = theme
+            .settings
+            .background
+            .map(|c| (c.r, c.g, c.b));
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let mut lines = Vec::new();
+
+        // Add code block header
+        lines.push(Line::from(Span::styled(
+            format!("┌─ {} ", lang.unwrap_or("code")),
+            Style::default().fg(Color::DarkGray),
+        )));
+
+        let gutter_width = if self.show_line_numbers {
+            code.lines().count().max(1).to_string().len()
+        } else {
+            0
+        };
+        let prefix_width = gutter_width + (gutter_width > 0) as usize + 2; // " │ " width
+
+        for (line_no, line) in LinesWithEndings::from(code).enumerate() {
+            let ranges = highlighter
+                .highlight_line(line, &self.syntax_set)
+                .unwrap_or_default();
+
+            let content_spans: Vec<Span<'static>> = ranges
+                .into_iter()
+                .map(|(style, text)| {
+                    Span::styled(
+                        text.trim_end_matches('\n').to_string(),
+                        self.syntect_to_ratatui_style(style, default_bg),
+                    )
+                })
+                .collect();
+
+            let gutter = self.line_gutter(line_no + 1, gutter_width);
+
+            let rows: Vec<Vec<Span<'static>>> = match (wrap_width, self.wrap_mode) {
+                (Some(width), TextWrapMode::Char | TextWrapMode::Word) => {
+                    let content_width = width.saturating_sub(prefix_width).max(1);
+                    wrap_spans(&content_spans, content_width, self.wrap_mode)
+                }
+                _ => vec![content_spans],
+            };
+
+            for (row_index, row) in rows.into_iter().enumerate() {
+                let mut spans = Vec::new();
+                if row_index == 0 {
+                    spans.extend(gutter.clone());
+                    spans.push(Span::styled("│ ", Style::default().fg(Color::DarkGray)));
                 } else {
-                    // Start code block
-                    in_code_block = true;
-                    let lang = line.trim_start_matches('`').trim();
-                    code_lang = if lang.is_empty() {
-                        None
-                    } else {
-                        Some(lang.to_string())
-                    };
+                    spans.push(Span::raw(" ".repeat(prefix_width)));
                 }
-                continue;
+                spans.extend(row);
+                lines.push(Line::from(spans));
             }
+        }
 
-            if in_code_block {
-                code_buffer.push(line);
-                continue;
-            }
+        // Add code block footer
+        lines.push(Line::from(Span::styled(
+            "└─────",
+            Style::default().fg(Color::DarkGray),
+        )));
 
-            lines.push(self.render_line(line));
-        }
+        lines
+    }
 
-        // Handle unclosed code block
-        if in_code_block && !code_buffer.is_empty() {
-            let code = code_buffer.join("\n");
-            let highlighted = self.highlight_code(&code, code_lang.as_deref());
-            lines.extend(highlighted);
+    /// Builds the right-aligned line-number span for source line `line_no`
+    /// (1-based), or an empty span list when the gutter is disabled.
+    fn line_gutter(&self, line_no: usize, gutter_width: usize) -> Vec<Span<'static>> {
+        if !self.show_line_numbers {
+            return Vec::new();
         }
-
-        Text::from(lines)
+        vec![Span::styled(
+            format!("{:>width$} ", line_no, width = gutter_width),
+            Style::default().fg(Color::DarkGray),
+        )]
     }
 
-    fn render_line<'a>(&self, line: &'a str) -> Line<'a> {
-        // Headers
-        if line.starts_with("######") {
-            return self.render_header(line.trim_start_matches('#').trim(), 6);
+    /// Converts a syntect highlight `Style` to a ratatui one, downsampling
+    /// colors to `self.color_depth` and carrying over bold/italic/underline
+    /// and a non-default background so themes that lean on font weight
+    /// (rather than just color) for emphasis still render distinctly.
+    fn syntect_to_ratatui_style(
+        &self,
+        style: SyntectStyle,
+        default_bg: Option<(u8, u8, u8)>,
+    ) -> Style {
+        let (r, g, b) = (style.foreground.r, style.foreground.g, style.foreground.b);
+        let fg = self.downsample(r, g, b);
+        let mut result = Style::default().fg(fg);
+
+        if style.font_style.contains(FontStyle::BOLD) {
+            result = result.add_modifier(Modifier::BOLD);
         }
-        if line.starts_with("#####") {
-            return self.render_header(line.trim_start_matches('#').trim(), 5);
+        if style.font_style.contains(FontStyle::ITALIC) {
+            result = result.add_modifier(Modifier::ITALIC);
         }
-        if line.starts_with("####") {
-            return self.render_header(line.trim_start_matches('#').trim(), 4);
+        if style.font_style.contains(FontStyle::UNDERLINE) {
+            result = result.add_modifier(Modifier::UNDERLINED);
         }
-        if line.starts_with("###") {
-            return self.render_header(line.trim_start_matches('#').trim(), 3);
+
+        let bg = style.background;
+        let bg_rgb = (bg.r, bg.g, bg.b);
+        if Some(bg_rgb) != default_bg {
+            result = result.bg(self.downsample(bg.r, bg.g, bg.b));
         }
-        if line.starts_with("##") {
-            return self.render_header(line.trim_start_matches('#').trim(), 2);
+
+        result
+    }
+
+    fn downsample(&self, r: u8, g: u8, b: u8) -> Color {
+        match self.color_depth {
+            ColorDepth::TrueColor => Color::Rgb(r, g, b),
+            ColorDepth::Ansi256 => Color::Indexed(nearest_256(r, g, b)),
+            ColorDepth::Ansi16 => nearest_16(r, g, b),
         }
-        if line.starts_with('#') {
-            return self.render_header(line.trim_start_matches('#').trim(), 1);
+    }
+}
+
+/// Falls back to `DEFAULT_THEME` (logging a warning) if `requested` isn't in
+/// `theme_set`, so an unknown `--theme` name or stale config value degrades
+/// gracefully instead of panicking on the indexing lookup in `highlight_code`.
+fn resolve_theme_name(theme_set: &ThemeSet, requested: &str) -> String {
+    if theme_set.themes.contains_key(requested) {
+        return requested.to_string();
+    }
+    if requested != DEFAULT_THEME {
+        tracing::warn!(requested, "unknown theme, falling back to default");
+    }
+    DEFAULT_THEME.to_string()
+}
+
+/// Decompresses and deserializes a `(SyntaxSet, ThemeSet)` pair from a
+/// zlib-compressed `bincode` dump, the format produced by syntect's own
+/// asset-packing helpers for bundling precompiled syntax/theme data.
+fn load_packed_assets(path: &Path) -> Result<(SyntaxSet, ThemeSet), Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(path)?;
+    let mut decoder = flate2::read::ZlibDecoder::new(file);
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes)?;
+    let (syntax_set, theme_set): (SyntaxSet, ThemeSet) = bincode::deserialize(&bytes)?;
+    Ok((syntax_set, theme_set))
+}
+
+/// Wraps a logical source line's styled spans into rows of at most `width`
+/// columns, splitting a span's text (and carrying its `Style` over to the
+/// continuation row) when it straddles a wrap point, rather than only
+/// breaking between spans.
+fn wrap_spans(
+    spans: &[Span<'static>],
+    width: usize,
+    mode: TextWrapMode,
+) -> Vec<Vec<Span<'static>>> {
+    if width == 0 {
+        return vec![spans.to_vec()];
+    }
+
+    let mut rows: Vec<Vec<Span<'static>>> = vec![Vec::new()];
+    let mut col = 0usize;
+
+    for span in spans {
+        let style = span.style;
+        let mut remaining = span.content.as_ref();
+
+        while !remaining.is_empty() {
+            let room = width.saturating_sub(col);
+            if room == 0 {
+                rows.push(Vec::new());
+                col = 0;
+                continue;
+            }
+
+            let split_at = match mode {
+                TextWrapMode::Word => word_wrap_point(remaining, room),
+                TextWrapMode::Char | TextWrapMode::NoWrap => char_wrap_point(remaining, room),
+            };
+
+            let (chunk, rest) = remaining.split_at(split_at);
+            if !chunk.is_empty() {
+                rows.last_mut().unwrap().push(Span::styled(chunk.to_string(), style));
+                col += chunk.chars().count();
+            }
+            remaining = rest;
+
+            if !remaining.is_empty() {
+                rows.push(Vec::new());
+                col = 0;
+            }
         }
+    }
+
+    rows
+}
+
+/// Byte offset of the first `max_chars` characters of `s` (or all of it, if
+/// shorter).
+fn char_wrap_point(s: &str, max_chars: usize) -> usize {
+    s.char_indices()
+        .nth(max_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(s.len())
+}
+
+/// Byte offset to split `s` at for word-wrapping within `max_chars` columns:
+/// the last space at or before the limit (consumed, so the next row starts
+/// clean), or a hard character split if the leading word itself exceeds
+/// `max_chars`.
+fn word_wrap_point(s: &str, max_chars: usize) -> usize {
+    let limit = char_wrap_point(s, max_chars);
+    if limit == s.len() {
+        return limit;
+    }
+    match s[..limit].rfind(' ') {
+        Some(pos) => pos + 1,
+        None => limit,
+    }
+}
+
+fn squared_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    dr * dr + dg * dg + db * db
+}
+
+/// Downsamples an RGB color to the nearest xterm 256-color palette entry:
+/// the nearer of the 6×6×6 color cube and the 24-step grayscale ramp.
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    let nearest_level = |c: u8| -> usize {
+        LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (**level as i32 - c as i32).abs())
+            .map(|(i, _)| i)
+            .unwrap()
+    };
+
+    let (r6, g6, b6) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube_rgb = (LEVELS[r6], LEVELS[g6], LEVELS[b6]);
+    let cube_index = 16 + 36 * r6 + 6 * g6 + b6;
+
+    let luma = 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+    let gray_step = (((luma - 8.0) / 10.0).round() as i32).clamp(0, 23);
+    let gray_value = (8 + gray_step * 10) as u8;
+    let gray_index = 232 + gray_step as u8;
+
+    if squared_distance((gray_value, gray_value, gray_value), (r, g, b))
+        < squared_distance(cube_rgb, (r, g, b))
+    {
+        gray_index
+    } else {
+        cube_index as u8
+    }
+}
+
+/// Downsamples an RGB color to the nearest standard 16-color ANSI entry.
+fn nearest_16(r: u8, g: u8, b: u8) -> Color {
+    const PALETTE: [(Color, (u8, u8, u8)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::Red, (128, 0, 0)),
+        (Color::Green, (0, 128, 0)),
+        (Color::Yellow, (128, 128, 0)),
+        (Color::Blue, (0, 0, 128)),
+        (Color::Magenta, (128, 0, 128)),
+        (Color::Cyan, (0, 128, 128)),
+        (Color::Gray, (192, 192, 192)),
+        (Color::DarkGray, (128, 128, 128)),
+        (Color::LightRed, (255, 0, 0)),
+        (Color::LightGreen, (0, 255, 0)),
+        (Color::LightYellow, (255, 255, 0)),
+        (Color::LightBlue, (0, 0, 255)),
+        (Color::LightMagenta, (255, 0, 255)),
+        (Color::LightCyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+
+    PALETTE
+        .iter()
+        .min_by_key(|(_, rgb)| squared_distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::White)
+}
+
+impl Default for MarkdownRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A prefix segment prepended to every rendered line while a block-quote or
+/// list item is open, so nested inline content (bold, links, inline code)
+/// still gets the surrounding indentation instead of losing it to a
+/// plain-text scan.
+enum PrefixPart {
+    BlockQuote,
+    /// A one-shot marker (bullet, number, task checkbox, footnote label)
+    /// shown on the item's first line; continuation lines get blank padding
+    /// of the same width instead.
+    Marker {
+        width: usize,
+        marker: Option<(String, Style)>,
+    },
+}
 
-        // Horizontal rule
-        if line.trim() == "---" || line.trim() == "***" || line.trim() == "___" {
-            return Line::from(Span::styled(
-                "─".repeat(40),
-                Style::default().fg(Color::DarkGray),
-            ));
+struct ListFrame {
+    ordered: bool,
+    index: u64,
+}
+
+/// Walks a `pulldown-cmark` event stream and lowers it into ratatui lines,
+/// tracking block nesting (list/blockquote/table) as an explicit stack
+/// instead of re-deriving it from indentation on every line.
+struct Builder<'r> {
+    renderer: &'r MarkdownRenderer,
+    lines: Vec<Line<'static>>,
+    block_ranges: Vec<Range<usize>>,
+    current: Vec<Span<'static>>,
+    prefix_stack: Vec<PrefixPart>,
+    list_stack: Vec<ListFrame>,
+    modifier_stack: Vec<Modifier>,
+    blockquote_depth: usize,
+    link_depth: usize,
+    current_heading_level: Option<HeadingLevel>,
+    awaiting_item_marker: bool,
+
+    in_code_block: bool,
+    code_lang: Option<String>,
+    code_buffer: String,
+    /// Target render width for code-block wrapping, set by
+    /// `render_wrapped_with_block_ranges`; `None` keeps `highlight_code`'s
+    /// one-line-per-source-line behavior.
+    wrap_width: Option<usize>,
+
+    table_alignments: Vec<Alignment>,
+    table_rows: Vec<Vec<String>>,
+    table_current_row: Vec<String>,
+    in_table_cell: bool,
+    table_current_cell: String,
+}
+
+impl<'r> Builder<'r> {
+    fn new(renderer: &'r MarkdownRenderer) -> Self {
+        Self {
+            renderer,
+            lines: Vec::new(),
+            block_ranges: Vec::new(),
+            current: Vec::new(),
+            prefix_stack: Vec::new(),
+            list_stack: Vec::new(),
+            modifier_stack: Vec::new(),
+            blockquote_depth: 0,
+            link_depth: 0,
+            current_heading_level: None,
+            awaiting_item_marker: false,
+            in_code_block: false,
+            code_lang: None,
+            code_buffer: String::new(),
+            wrap_width: None,
+            table_alignments: Vec::new(),
+            table_rows: Vec::new(),
+            table_current_row: Vec::new(),
+            in_table_cell: false,
+            table_current_cell: String::new(),
         }
+    }
 
-        // Blockquote
-        if line.starts_with('>') {
-            let content = line.trim_start_matches('>').trim();
-            return Line::from(vec![
-                Span::styled("│ ", Style::default().fg(Color::DarkGray)),
-                Span::styled(
-                    content.to_string(),
-                    Style::default()
-                        .fg(Color::Gray)
-                        .add_modifier(Modifier::ITALIC),
-                ),
-            ]);
+    fn handle_event(&mut self, event: Event<'_>) {
+        // A task-list checkbox is the one event that must win the race for
+        // an item's marker slot, so it's handled before `ensure_item_marker`
+        // would otherwise fill it with a bullet/number.
+        if let Event::TaskListMarker(checked) = event {
+            self.awaiting_item_marker = false;
+            let marker = if checked { "[x] " } else { "[ ] " }.to_string();
+            let width = marker.chars().count();
+            self.prefix_stack.push(PrefixPart::Marker {
+                width,
+                marker: Some((marker, Style::default().fg(Color::Green))),
+            });
+            return;
         }
 
-        // Unordered list
-        if line.trim_start().starts_with("- ")
-            || line.trim_start().starts_with("* ")
-            || line.trim_start().starts_with("+ ")
-        {
-            let indent = line.len() - line.trim_start().len();
-            let content = line.trim_start()[2..].to_string();
-            let bullet_indent = " ".repeat(indent);
-            return Line::from(vec![
-                Span::raw(bullet_indent),
-                Span::styled("• ", Style::default().fg(Color::Cyan)),
-                Span::raw(content),
-            ]);
-        }
-
-        // Ordered list
-        if let Some(rest) = self.try_parse_ordered_list(line) {
-            let indent = line.len() - line.trim_start().len();
-            let bullet_indent = " ".repeat(indent);
-            return Line::from(vec![
-                Span::raw(bullet_indent),
-                Span::styled(rest.0, Style::default().fg(Color::Cyan)),
-                Span::raw(rest.1.to_string()),
-            ]);
-        }
-
-        // Table row
-        if line.contains('|') && line.trim().starts_with('|') {
-            return self.render_table_row(line);
-        }
-
-        // Regular paragraph with inline formatting
-        self.render_inline(line)
-    }
-
-    fn render_header(&self, content: &str, level: u8) -> Line<'static> {
-        let (color, prefix) = match level {
-            1 => (Color::Magenta, "█ "),
-            2 => (Color::Cyan, "▓ "),
-            3 => (Color::Blue, "▒ "),
-            _ => (Color::Gray, "░ "),
-        };
+        self.ensure_item_marker();
 
-        Line::from(vec![
-            Span::styled(prefix, Style::default().fg(color)),
-            Span::styled(
-                content.to_string(),
-                Style::default().fg(color).add_modifier(Modifier::BOLD),
-            ),
-        ])
-    }
-
-    fn render_inline<'a>(&self, text: &'a str) -> Line<'a> {
-        let mut spans: Vec<Span> = Vec::new();
-        let mut chars = text.char_indices().peekable();
-        let mut current_start = 0;
-
-        while let Some((i, c)) = chars.next() {
-            match c {
-                '`' => {
-                    // Inline code
-                    if current_start < i {
-                        spans.push(Span::raw(&text[current_start..i]));
-                    }
-                    let code_start = i + 1;
-                    let mut code_end = code_start;
-                    for (j, ch) in chars.by_ref() {
-                        if ch == '`' {
-                            code_end = j;
-                            break;
-                        }
-                        code_end = j + ch.len_utf8();
-                    }
-                    if code_end > code_start {
-                        spans.push(Span::styled(
-                            text[code_start..code_end].to_string(),
-                            Style::default()
-                                .fg(Color::Yellow)
-                                .bg(Color::Rgb(40, 40, 40)),
-                        ));
-                    }
-                    current_start = code_end + 1;
-                }
-                '*' | '_' => {
-                    // Check for bold or italic
-                    if let Some((_, next_c)) = chars.peek()
-                        && *next_c == c
-                    {
-                        // Bold **text** or __text__
-                        if current_start < i {
-                            spans.push(Span::raw(&text[current_start..i]));
-                        }
-                        chars.next(); // consume second marker
-                        let bold_start = i + 2;
-                        let mut bold_end = bold_start;
-                        #[allow(clippy::while_let_on_iterator)]
-                        while let Some((j, ch)) = chars.next() {
-                            if ch == c
-                                && let Some((_, next_ch)) = chars.peek()
-                                && *next_ch == c
-                            {
-                                chars.next();
-                                bold_end = j;
-                                break;
-                            }
-                            bold_end = j + ch.len_utf8();
-                        }
-                        if bold_end > bold_start && bold_end <= text.len() {
-                            spans.push(Span::styled(
-                                text[bold_start..bold_end].to_string(),
-                                Style::default().add_modifier(Modifier::BOLD),
-                            ));
-                        }
-                        current_start = (bold_end + 2).min(text.len());
-                    } else if chars.peek().is_some() {
-                        // Italic *text* or _text_
-                        if current_start < i {
-                            spans.push(Span::raw(&text[current_start..i]));
-                        }
-                        let italic_start = i + 1;
-                        let mut italic_end = italic_start;
-                        for (j, ch) in chars.by_ref() {
-                            if ch == c {
-                                italic_end = j;
-                                break;
-                            }
-                            italic_end = j + ch.len_utf8();
-                        }
-                        if italic_end > italic_start && italic_end <= text.len() {
-                            spans.push(Span::styled(
-                                text[italic_start..italic_end].to_string(),
-                                Style::default().add_modifier(Modifier::ITALIC),
-                            ));
-                        }
-                        current_start = (italic_end + 1).min(text.len());
-                    }
+        match event {
+            Event::Start(tag) => self.start_tag(tag),
+            Event::End(tag_end) => self.end_tag(tag_end),
+            Event::Text(text) => {
+                if self.in_code_block {
+                    self.code_buffer.push_str(&text);
+                } else if self.in_table_cell {
+                    self.table_current_cell.push_str(&text);
+                } else {
+                    let style = self.text_style();
+                    self.current.push(Span::styled(text.into_string(), style));
                 }
-                '[' => {
-                    // Link [text](url)
-                    if current_start < i {
-                        spans.push(Span::raw(&text[current_start..i]));
-                    }
-                    let link_text_start = i + 1;
-                    let mut link_text_end = link_text_start;
-                    let mut found_close = false;
-                    for (j, ch) in chars.by_ref() {
-                        if ch == ']' {
-                            link_text_end = j;
-                            found_close = true;
-                            break;
-                        }
-                    }
-                    if found_close {
-                        if let Some((_, '(')) = chars.peek() {
-                            chars.next();
-                            let mut url_end = link_text_end + 2;
-                            for (j, ch) in chars.by_ref() {
-                                if ch == ')' {
-                                    url_end = j;
-                                    break;
-                                }
-                            }
-                            spans.push(Span::styled(
-                                text[link_text_start..link_text_end].to_string(),
-                                Style::default()
-                                    .fg(Color::Blue)
-                                    .add_modifier(Modifier::UNDERLINED),
-                            ));
-                            current_start = url_end + 1;
-                        } else {
-                            spans.push(Span::raw(&text[i..link_text_end + 1]));
-                            current_start = link_text_end + 1;
-                        }
-                    } else {
-                        current_start = i;
-                    }
+            }
+            Event::Code(text) => {
+                if self.in_table_cell {
+                    self.table_current_cell.push_str(&text);
+                } else {
+                    self.current.push(Span::styled(
+                        text.into_string(),
+                        Style::default().fg(Color::Yellow).bg(Color::Rgb(40, 40, 40)),
+                    ));
                 }
-                '~' => {
-                    // Strikethrough ~~text~~
-                    if let Some((_, '~')) = chars.peek() {
-                        if current_start < i {
-                            spans.push(Span::raw(&text[current_start..i]));
-                        }
-                        chars.next();
-                        let strike_start = i + 2;
-                        let mut strike_end = strike_start;
-                        #[allow(clippy::while_let_on_iterator)]
-                        while let Some((j, ch)) = chars.next() {
-                            if ch == '~'
-                                && let Some((_, '~')) = chars.peek()
-                            {
-                                chars.next();
-                                strike_end = j;
-                                break;
-                            }
-                            strike_end = j + ch.len_utf8();
-                        }
-                        if strike_end > strike_start && strike_end <= text.len() {
-                            spans.push(Span::styled(
-                                text[strike_start..strike_end].to_string(),
-                                Style::default().add_modifier(Modifier::CROSSED_OUT),
-                            ));
-                        }
-                        current_start = (strike_end + 2).min(text.len());
-                    }
+            }
+            Event::FootnoteReference(label) => {
+                self.current.push(Span::styled(
+                    format!("[^{}]", label),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC),
+                ));
+            }
+            Event::SoftBreak => {
+                if self.in_table_cell {
+                    self.table_current_cell.push(' ');
+                } else {
+                    self.current.push(Span::raw(" "));
                 }
-                _ => {}
             }
+            Event::HardBreak => self.push_line(),
+            Event::Rule => {
+                self.current = vec![Span::styled(
+                    "─".repeat(40),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                self.push_line();
+            }
+            Event::TaskListMarker(_) => unreachable!("handled above"),
+            _ => {}
         }
+    }
 
-        if current_start < text.len() {
-            spans.push(Span::raw(&text[current_start..]));
+    /// Resolves a pending list-item marker the first time the item produces
+    /// any content (other than a task checkbox, which resolves itself).
+    fn ensure_item_marker(&mut self) {
+        if !self.awaiting_item_marker {
+            return;
         }
-
-        if spans.is_empty() {
-            Line::from("")
-        } else {
-            Line::from(spans)
+        self.awaiting_item_marker = false;
+        if let Some(frame) = self.list_stack.last_mut() {
+            let marker = if frame.ordered {
+                let n = frame.index;
+                frame.index += 1;
+                format!("{}. ", n)
+            } else {
+                "• ".to_string()
+            };
+            let width = marker.chars().count();
+            self.prefix_stack.push(PrefixPart::Marker {
+                width,
+                marker: Some((marker, Style::default().fg(Color::Cyan))),
+            });
         }
     }
 
-    fn render_table_row(&self, line: &str) -> Line<'static> {
-        let trimmed = line.trim();
+    fn text_style(&self) -> Style {
+        if let Some(level) = self.current_heading_level {
+            return Style::default()
+                .fg(heading_color(level))
+                .add_modifier(Modifier::BOLD);
+        }
 
-        // Check if separator row
-        if trimmed
-            .chars()
-            .all(|c| c == '|' || c == '-' || c == ':' || c == ' ')
-        {
-            return Line::from(Span::styled(
-                "─".repeat(trimmed.len().min(60)),
-                Style::default().fg(Color::DarkGray),
-            ));
+        let mut modifiers = Modifier::empty();
+        for m in &self.modifier_stack {
+            modifiers |= *m;
         }
+        let style = Style::default().add_modifier(modifiers);
 
-        let cells: Vec<&str> = trimmed
-            .trim_matches('|')
-            .split('|')
-            .map(|s| s.trim())
-            .collect();
+        if self.link_depth > 0 {
+            style.fg(Color::Blue).add_modifier(Modifier::UNDERLINED)
+        } else if self.blockquote_depth > 0 {
+            style.fg(Color::Gray).add_modifier(Modifier::ITALIC)
+        } else {
+            style
+        }
+    }
 
-        let mut spans: Vec<Span> = vec![Span::styled("│", Style::default().fg(Color::DarkGray))];
-        for cell in cells {
-            spans.push(Span::raw(format!(" {} ", cell)));
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+    fn start_tag(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.current_heading_level = Some(level);
+            }
+            Tag::BlockQuote(_) => {
+                self.blockquote_depth += 1;
+                self.prefix_stack.push(PrefixPart::BlockQuote);
+            }
+            Tag::CodeBlock(kind) => {
+                self.in_code_block = true;
+                self.code_lang = match kind {
+                    CodeBlockKind::Fenced(lang) if !lang.is_empty() => Some(lang.to_string()),
+                    _ => None,
+                };
+                self.code_buffer.clear();
+            }
+            Tag::List(start) => {
+                self.list_stack.push(ListFrame {
+                    ordered: start.is_some(),
+                    index: start.unwrap_or(1),
+                });
+            }
+            Tag::Item => {
+                self.awaiting_item_marker = true;
+            }
+            Tag::FootnoteDefinition(label) => {
+                let marker = format!("[^{}]: ", label);
+                let width = marker.chars().count();
+                self.prefix_stack.push(PrefixPart::Marker {
+                    width,
+                    marker: Some((marker, Style::default().fg(Color::Yellow))),
+                });
+            }
+            Tag::Table(alignments) => {
+                self.table_alignments = alignments;
+                self.table_rows.clear();
+            }
+            Tag::TableHead | Tag::TableRow => {
+                self.table_current_row.clear();
+            }
+            Tag::TableCell => {
+                self.in_table_cell = true;
+                self.table_current_cell.clear();
+            }
+            Tag::Emphasis => self.modifier_stack.push(Modifier::ITALIC),
+            Tag::Strong => self.modifier_stack.push(Modifier::BOLD),
+            Tag::Strikethrough => self.modifier_stack.push(Modifier::CROSSED_OUT),
+            Tag::Link { .. } | Tag::Image { .. } => self.link_depth += 1,
+            _ => {}
+        }
+    }
+
+    fn end_tag(&mut self, tag_end: TagEnd) {
+        match tag_end {
+            TagEnd::Paragraph => self.push_line(),
+            TagEnd::Heading(level) => {
+                let mut spans = vec![Span::styled(
+                    heading_marker(level),
+                    Style::default().fg(heading_color(level)),
+                )];
+                spans.extend(std::mem::take(&mut self.current));
+                self.current = spans;
+                self.current_heading_level = None;
+                self.push_line();
+            }
+            TagEnd::BlockQuote => {
+                self.blockquote_depth = self.blockquote_depth.saturating_sub(1);
+                self.prefix_stack.pop();
+            }
+            TagEnd::CodeBlock => self.flush_code_block(),
+            TagEnd::List(_) => {
+                self.list_stack.pop();
+            }
+            TagEnd::Item => {
+                // A tight list (no blank lines between items) never emits a
+                // `Paragraph` for its items, so their text only ever lands in
+                // `self.current`. Flush it as the item's own line before the
+                // marker prefix is popped; for a loose list `Paragraph` has
+                // already done this and `self.current` is empty here.
+                if !self.current.is_empty() {
+                    self.push_line();
+                }
+                self.prefix_stack.pop();
+            }
+            TagEnd::FootnoteDefinition => {
+                self.prefix_stack.pop();
+            }
+            TagEnd::Table => self.flush_table(),
+            TagEnd::TableHead => {
+                self.table_rows.push(std::mem::take(&mut self.table_current_row));
+            }
+            TagEnd::TableRow => {
+                self.table_rows.push(std::mem::take(&mut self.table_current_row));
+            }
+            TagEnd::TableCell => {
+                self.in_table_cell = false;
+                let cell = std::mem::take(&mut self.table_current_cell);
+                self.table_current_row.push(cell);
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.modifier_stack.pop();
+            }
+            TagEnd::Link | TagEnd::Image => {
+                self.link_depth = self.link_depth.saturating_sub(1);
+            }
+            _ => {}
         }
+    }
 
-        Line::from(spans)
+    fn flush_code_block(&mut self) {
+        let start = self.lines.len();
+        let code = std::mem::take(&mut self.code_buffer);
+        let highlighted =
+            self.renderer
+                .highlight_code(&code, self.code_lang.as_deref(), self.wrap_width);
+        self.lines.extend(highlighted);
+        self.block_ranges.push(start..self.lines.len());
+        self.in_code_block = false;
+        self.code_lang = None;
     }
 
-    fn try_parse_ordered_list<'a>(&self, line: &'a str) -> Option<(String, &'a str)> {
-        let trimmed = line.trim_start();
-        let mut num_end = 0;
-        for (i, c) in trimmed.char_indices() {
-            if c.is_ascii_digit() {
-                num_end = i + 1;
-            } else if c == '.' && num_end > 0 && i == num_end {
-                if trimmed.get(i + 1..i + 2) == Some(" ") {
-                    let number = &trimmed[..num_end];
-                    let content = &trimmed[i + 2..];
-                    return Some((format!("{}. ", number), content));
+    fn flush_table(&mut self) {
+        let rows = std::mem::take(&mut self.table_rows);
+        let alignments = std::mem::take(&mut self.table_alignments);
+        self.lines.extend(render_table_lines(&rows, &alignments));
+    }
+
+    fn push_line(&mut self) {
+        let mut spans = Vec::new();
+        for part in &mut self.prefix_stack {
+            match part {
+                PrefixPart::BlockQuote => {
+                    spans.push(Span::styled("│ ", Style::default().fg(Color::DarkGray)));
+                }
+                PrefixPart::Marker { width, marker } => {
+                    if let Some((text, style)) = marker.take() {
+                        spans.push(Span::styled(text, style));
+                    } else {
+                        spans.push(Span::raw(" ".repeat(*width)));
+                    }
                 }
-            } else {
-                break;
             }
         }
-        None
+        spans.extend(std::mem::take(&mut self.current));
+        self.lines.push(Line::from(spans));
     }
 
-    fn highlight_code(&self, code: &str, lang: Option<&str>) -> Vec<Line<'static>> {
-        let syntax = lang
-            .and_then(|l| self.syntax_set.find_syntax_by_token(l))
-            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+    fn finish(mut self) -> (Text<'static>, Vec<Range<usize>>) {
+        if !self.current.is_empty() {
+            self.push_line();
+        }
+        (Text::from(self.lines), self.block_ranges)
+    }
+}
 
-        let theme = &self.theme_set.themes["base16-ocean.dark"];
-        let mut highlighter = HighlightLines::new(syntax, theme);
-        let mut lines = Vec::new();
+fn heading_color(level: HeadingLevel) -> Color {
+    match level {
+        HeadingLevel::H1 => Color::Magenta,
+        HeadingLevel::H2 => Color::Cyan,
+        HeadingLevel::H3 => Color::Blue,
+        _ => Color::Gray,
+    }
+}
 
-        // Add code block header
-        lines.push(Line::from(Span::styled(
-            format!("┌─ {} ", lang.unwrap_or("code")),
-            Style::default().fg(Color::DarkGray),
-        )));
+fn heading_marker(level: HeadingLevel) -> &'static str {
+    match level {
+        HeadingLevel::H1 => "█ ",
+        HeadingLevel::H2 => "▓ ",
+        HeadingLevel::H3 => "▒ ",
+        _ => "░ ",
+    }
+}
 
-        for line in LinesWithEndings::from(code) {
-            let ranges = highlighter
-                .highlight_line(line, &self.syntax_set)
-                .unwrap_or_default();
+fn render_table_lines(rows: &[Vec<String>], alignments: &[Alignment]) -> Vec<Line<'static>> {
+    if rows.is_empty() {
+        return Vec::new();
+    }
 
-            let mut spans: Vec<Span> =
-                vec![Span::styled("│ ", Style::default().fg(Color::DarkGray))];
+    let col_count = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut widths = vec![0usize; col_count];
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
 
-            for (style, text) in ranges {
-                spans.push(Span::styled(
-                    text.trim_end_matches('\n').to_string(),
-                    syntect_to_ratatui_style(style),
+    let mut lines = Vec::new();
+    for (row_index, row) in rows.iter().enumerate() {
+        let mut spans = vec![Span::styled("│", Style::default().fg(Color::DarkGray))];
+        for (i, width) in widths.iter().enumerate() {
+            let cell = row.get(i).map(String::as_str).unwrap_or("");
+            let align = alignments.get(i).copied().unwrap_or(Alignment::None);
+            spans.push(Span::raw(format!(" {} ", pad_cell(cell, *width, align))));
+            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+        }
+        lines.push(Line::from(spans));
+
+        if row_index == 0 {
+            let mut sep_spans = vec![Span::styled("│", Style::default().fg(Color::DarkGray))];
+            for width in &widths {
+                sep_spans.push(Span::styled(
+                    "─".repeat(width + 2),
+                    Style::default().fg(Color::DarkGray),
                 ));
+                sep_spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
             }
-
-            lines.push(Line::from(spans));
+            lines.push(Line::from(sep_spans));
         }
+    }
+    lines
+}
 
-        // Add code block footer
-        lines.push(Line::from(Span::styled(
-            "└─────",
-            Style::default().fg(Color::DarkGray),
-        )));
-
-        lines
+fn pad_cell(cell: &str, width: usize, align: Alignment) -> String {
+    let len = cell.chars().count();
+    let pad = width.saturating_sub(len);
+    match align {
+        Alignment::Right => format!("{}{}", " ".repeat(pad), cell),
+        Alignment::Center => {
+            let left = pad / 2;
+            let right = pad - left;
+            format!("{}{}{}", " ".repeat(left), cell, " ".repeat(right))
+        }
+        _ => format!("{}{}", cell, " ".repeat(pad)),
     }
 }
 
-fn syntect_to_ratatui_style(style: SyntectStyle) -> Style {
-    let fg = Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b);
-    Style::default().fg(fg)
+/// Extracts the raw contents of each fenced code block in `markdown`, in
+/// document order — used by the transcript's copy-to-clipboard selection,
+/// which copies a block's source rather than its syntax-highlighted render.
+pub fn extract_code_blocks(markdown: &str) -> Vec<String> {
+    let parser = Parser::new_ext(markdown, parser_options());
+    let mut blocks = Vec::new();
+    let mut in_code_block = false;
+    let mut buffer = String::new();
+
+    for event in parser {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                buffer.clear();
+            }
+            Event::Text(text) if in_code_block => buffer.push_str(&text),
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                blocks.push(std::mem::take(&mut buffer));
+            }
+            _ => {}
+        }
+    }
+    blocks
 }
 
-impl Default for MarkdownRenderer {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered_lines(markdown: &str) -> Vec<String> {
+        MarkdownRenderer::new()
+            .render(markdown)
+            .lines
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>())
+            .collect()
+    }
+
+    /// A tight list (no blank lines between items) never emits a `Paragraph`
+    /// event, so each item's text must still render as its own bulleted
+    /// line instead of being concatenated onto the previous item.
+    #[test]
+    fn tight_list_items_render_as_separate_bullets() {
+        let lines = rendered_lines("- a\n- b\n- c\n");
+        assert_eq!(lines, vec!["• a", "• b", "• c"]);
+    }
+
+    /// A loose list (blank line between items) does go through `Paragraph`
+    /// and must keep rendering the same as before this fix.
+    #[test]
+    fn loose_list_items_render_as_separate_bullets() {
+        let lines = rendered_lines("- a\n\n- b\n");
+        assert_eq!(lines, vec!["• a", "• b"]);
     }
 }
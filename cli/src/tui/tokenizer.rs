@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+/// A tiny subset of common byte-pair merges, loosely modeled on the GPT-2/cl100k
+/// merge tables. This is not byte-accurate to any real tokenizer — it only needs
+/// to give users a ballpark cost signal before they spend an API call.
+const MERGE_TABLE: &[&str] = &[
+    "th", "he", "in", "er", "an", "re", "on", "at", "en", "nd", "ti", "es", "or", "te", "of", "ed",
+    "is", "it", "al", "ar", "st", "to", "nt", "ng", " t", " a", " s", " w", "ou", "ea", "the",
+    "ing", "ion", "and", "er ", "he ", "in ", "to ", " th", " wh", "tha", "ere", " re", " de",
+];
+
+/// Rough per-model context window sizes, used to color the budget estimate.
+fn context_window_for_model(model: &str) -> u32 {
+    match model {
+        m if m.starts_with("gpt-4o") => 128_000,
+        m if m.starts_with("gpt-4-turbo") => 128_000,
+        m if m.starts_with("gpt-4") => 8_192,
+        m if m.starts_with("gpt-3.5") => 16_385,
+        m if m.starts_with("o1") || m.starts_with("o3") => 200_000,
+        _ => 32_768,
+    }
+}
+
+/// A lightweight, client-side BPE-style token counter used for the status-bar
+/// budget estimate. It is deliberately approximate: a real tokenizer's merge
+/// table has tens of thousands of entries, but a handful of the most common
+/// English bigrams gets within shouting distance for the UI's purposes.
+pub struct TokenEstimator {
+    ranks: HashMap<Vec<u8>, usize>,
+    split_re: Regex,
+}
+
+impl TokenEstimator {
+    pub fn new() -> Self {
+        let ranks = MERGE_TABLE
+            .iter()
+            .enumerate()
+            .map(|(rank, merge)| (merge.as_bytes().to_vec(), rank))
+            .collect();
+        let split_re = Regex::new(
+            r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+",
+        )
+        .expect("token regex is valid");
+        Self { ranks, split_re }
+    }
+
+    /// Estimates the token count of `text`.
+    pub fn count(&self, text: &str) -> usize {
+        self.split_re
+            .find_iter(text)
+            .map(|m| self.count_piece(m.as_str()))
+            .sum()
+    }
+
+    fn count_piece(&self, piece: &str) -> usize {
+        let mut symbols: Vec<Vec<u8>> = piece.bytes().map(|b| vec![b]).collect();
+        if symbols.is_empty() {
+            return 0;
+        }
+
+        loop {
+            let mut best: Option<(usize, usize)> = None; // (pair index, rank)
+            for i in 0..symbols.len().saturating_sub(1) {
+                let mut pair = symbols[i].clone();
+                pair.extend_from_slice(&symbols[i + 1]);
+                if let Some(&rank) = self.ranks.get(&pair)
+                    && best.is_none_or(|(_, best_rank)| rank < best_rank)
+                {
+                    best = Some((i, rank));
+                }
+            }
+
+            match best {
+                Some((i, _)) => {
+                    let mut merged = symbols[i].clone();
+                    merged.extend_from_slice(&symbols[i + 1]);
+                    symbols.splice(i..=i + 1, [merged]);
+                }
+                None => break,
+            }
+        }
+
+        symbols.len()
+    }
+}
+
+impl Default for TokenEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A red/yellow/green classification of `used` tokens against a model's
+/// context window, for coloring the status-bar estimate.
+pub enum BudgetLevel {
+    Low,
+    Medium,
+    High,
+}
+
+pub fn budget_level(used: usize, model: &str) -> BudgetLevel {
+    let window = context_window_for_model(model) as usize;
+    let ratio = used as f64 / window as f64;
+    if ratio >= 0.75 {
+        BudgetLevel::High
+    } else if ratio >= 0.4 {
+        BudgetLevel::Medium
+    } else {
+        BudgetLevel::Low
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style, Stylize};
@@ -5,35 +7,105 @@ use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
 use super::App;
-use super::markdown::MarkdownRenderer;
+use super::tokenizer::{BudgetLevel, budget_level};
+
+#[derive(Clone)]
+pub enum ToolStatus {
+    Running,
+    Success,
+    Error(String),
+}
 
 #[derive(Clone)]
 pub enum MessageRole {
     User,
     Assistant,
     System,
+    Tool {
+        name: String,
+        args: String,
+        status: ToolStatus,
+    },
 }
 
 #[derive(Clone)]
 pub struct ChatMessage {
     pub role: MessageRole,
     pub content: String,
+    /// Whether a large body (currently only tool output) is collapsed in the transcript.
+    pub folded: bool,
+    /// Cached estimated token count of `content`, filled lazily by `App::estimated_tokens`.
+    pub token_count: Cell<Option<usize>>,
 }
 
-pub fn render_ui(frame: &mut Frame, app: &App) {
-    let chunks = Layout::vertical([
-        Constraint::Min(1),
-        Constraint::Length(3),
-        Constraint::Length(1),
-    ])
-    .split(frame.area());
+pub(super) fn render_run_browser(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(state) = &app.browser else {
+        return;
+    };
+
+    let popup = centered_rect(70, 60, area);
+    frame.render_widget(ratatui::widgets::Clear, popup);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Run Browser ")
+        .title_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    if state.runs.is_empty() {
+        let empty = Paragraph::new("No runs found under runs/.")
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(empty, inner);
+        return;
+    }
 
-    render_chat_area(frame, app, chunks[0]);
-    render_input(frame, app, chunks[1]);
-    render_status_bar(frame, app, chunks[2]);
+    let lines: Vec<Line> = state
+        .runs
+        .iter()
+        .enumerate()
+        .map(|(index, run)| {
+            let model = run.model.as_deref().unwrap_or("unknown model");
+            let tokens = run
+                .total_tokens
+                .map(|t| format!("{} tok", t))
+                .unwrap_or_else(|| "? tok".to_string());
+            let text = format!(
+                "{}  {}  {}  {}ms",
+                &run.run_id[..run.run_id.len().min(8)],
+                model,
+                tokens,
+                run.duration_ms
+            );
+            Line::from(Span::styled(text, line_style(Color::White, index == state.selected)))
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(Text::from(lines));
+    frame.render_widget(paragraph, inner);
 }
 
-fn render_chat_area(frame: &mut Frame, app: &App, area: Rect) {
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(area);
+    Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(vertical[1])[1]
+}
+
+pub(super) fn render_chat_area(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray))
@@ -75,11 +147,13 @@ fn render_chat_area(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    let md_renderer = MarkdownRenderer::new();
+    let md_renderer = &app.md_renderer;
+    let render_width = inner.width as usize;
     let mut lines: Vec<Line> = Vec::new();
 
-    for msg in &app.messages {
-        match msg.role {
+    for (index, msg) in app.messages.iter().enumerate() {
+        let selected = app.selected_message == Some(index);
+        match &msg.role {
             MessageRole::User => {
                 lines.push(Line::from(vec![
                     Span::styled(
@@ -88,25 +162,50 @@ fn render_chat_area(frame: &mut Frame, app: &App, area: Rect) {
                             .fg(Color::Green)
                             .add_modifier(Modifier::BOLD),
                     ),
-                    Span::styled(&msg.content, Style::default().fg(Color::White)),
+                    Span::styled(&msg.content, line_style(Color::White, selected)),
                 ]));
             }
             MessageRole::System => {
                 lines.push(Line::from(vec![
                     Span::styled("→ ", Style::default().fg(Color::Yellow)),
-                    Span::styled(&msg.content, Style::default().fg(Color::DarkGray)),
+                    Span::styled(&msg.content, line_style(Color::DarkGray, selected)),
                 ]));
             }
             MessageRole::Assistant => {
                 lines.push(Line::from(Span::styled(
                     "Lode:",
-                    Style::default()
-                        .fg(Color::Cyan)
+                    line_style(Color::Cyan, selected && app.selected_code_block.is_none())
                         .add_modifier(Modifier::BOLD),
                 )));
-                let rendered = md_renderer.render(&msg.content);
-                for line in rendered.lines {
-                    lines.push(line);
+                let (rendered, block_ranges) =
+                    md_renderer.render_wrapped_with_block_ranges(&msg.content, render_width);
+                let highlight = if selected { app.selected_code_block } else { None }
+                    .and_then(|i| block_ranges.get(i));
+                for (i, line) in rendered.lines.into_iter().enumerate() {
+                    if highlight.is_some_and(|range| range.contains(&i)) {
+                        lines.push(reverse_line(line));
+                    } else {
+                        lines.push(line);
+                    }
+                }
+            }
+            MessageRole::Tool { name, args, status } => {
+                lines.push(tool_header_line(name, args, status, selected));
+                if msg.folded {
+                    if !msg.content.is_empty() {
+                        let fold_count = msg.content.lines().count();
+                        lines.push(Line::from(Span::styled(
+                            format!("  [{} lines folded, press Ctrl+F to expand]", fold_count),
+                            Style::default().fg(Color::DarkGray),
+                        )));
+                    }
+                } else {
+                    for line in msg.content.lines() {
+                        lines.push(Line::from(Span::styled(
+                            format!("  {}", line),
+                            Style::default().fg(Color::Gray),
+                        )));
+                    }
                 }
             }
         }
@@ -134,6 +233,56 @@ fn render_chat_area(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, inner);
 }
 
+fn line_style(color: Color, selected: bool) -> Style {
+    let style = Style::default().fg(color);
+    if selected {
+        style.add_modifier(Modifier::REVERSED)
+    } else {
+        style
+    }
+}
+
+/// Applies a reversed style to every span in `line`, used to highlight the
+/// code block a user has selected for copying.
+fn reverse_line(line: Line<'_>) -> Line<'_> {
+    Line::from(
+        line.spans
+            .into_iter()
+            .map(|span| Span::styled(span.content, span.style.add_modifier(Modifier::REVERSED)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn tool_header_line<'a>(
+    name: &'a str,
+    args: &'a str,
+    status: &ToolStatus,
+    selected: bool,
+) -> Line<'a> {
+    let (marker, marker_color) = match status {
+        ToolStatus::Running => ("⚙ ", Color::Yellow),
+        ToolStatus::Success => ("✓ ", Color::Green),
+        ToolStatus::Error(_) => ("✗ ", Color::Red),
+    };
+
+    let mut spans = vec![
+        Span::styled(marker, Style::default().fg(marker_color)),
+        Span::styled(
+            format!("{}(\"{}\")", name, args),
+            line_style(Color::Cyan, selected).add_modifier(Modifier::BOLD),
+        ),
+    ];
+
+    if let ToolStatus::Error(reason) = status {
+        spans.push(Span::styled(
+            format!(" — {}", reason),
+            Style::default().fg(Color::Red),
+        ));
+    }
+
+    Line::from(spans)
+}
+
 fn wrapped_line_count(line: &Line, width: usize) -> usize {
     if width == 0 {
         return 1;
@@ -146,12 +295,12 @@ fn wrapped_line_count(line: &Line, width: usize) -> usize {
 }
 
 pub fn calculate_total_lines(app: &App, width: u16) -> usize {
-    let md_renderer = MarkdownRenderer::new();
+    let md_renderer = &app.md_renderer;
     let available_width = width.saturating_sub(2) as usize; // account for borders
     let mut count = 0;
 
     for msg in &app.messages {
-        match msg.role {
+        match &msg.role {
             MessageRole::User => {
                 let prefix_len = 5; // "You: "
                 let content_len = msg.content.chars().count();
@@ -179,13 +328,35 @@ pub fn calculate_total_lines(app: &App, width: u16) -> usize {
                     count += wrapped_line_count(line, available_width);
                 }
             }
+            MessageRole::Tool { name, args, .. } => {
+                let header_len = name.chars().count() + args.chars().count() + 6; // marker + parens/quotes
+                count += if available_width > 0 {
+                    header_len.div_ceil(available_width)
+                } else {
+                    1
+                };
+                if msg.folded {
+                    if !msg.content.is_empty() {
+                        count += 1; // "[N lines folded]" summary line
+                    }
+                } else {
+                    for line in msg.content.lines() {
+                        let line_len = line.chars().count() + 2;
+                        count += if available_width > 0 {
+                            line_len.div_ceil(available_width)
+                        } else {
+                            1
+                        };
+                    }
+                }
+            }
         }
         count += 1; // blank line after each message
     }
     count
 }
 
-fn render_input(frame: &mut Frame, app: &App, area: Rect) {
+pub(super) fn render_input(frame: &mut Frame, app: &App, area: Rect) {
     let (border_color, title) = if app.is_clarifying() {
         if let Some(q) = app.current_question() {
             (Color::Magenta, format!(" {} ", q.label))
@@ -209,34 +380,61 @@ fn render_input(frame: &mut Frame, app: &App, area: Rect) {
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let display_text = if app.is_clarifying() {
-        format!("{}▏", app.input)
-    } else if app.awaiting_confirmation() {
-        format!("{}▏", app.input)
-    } else if app.is_processing {
-        app.status.clone().unwrap_or_default()
-    } else {
-        format!("{}▏", app.input)
-    };
+    if !app.is_editing() {
+        let paragraph = Paragraph::new(Span::styled(
+            app.status.clone().unwrap_or_default(),
+            Style::default().fg(Color::DarkGray).italic(),
+        ));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
 
-    let text_style = if app.is_processing && !app.is_clarifying() && !app.awaiting_confirmation() {
-        Style::default().fg(Color::DarkGray).italic()
+    let paragraph = Paragraph::new(input_line(app.input_text(), app.input_cursor()));
+    frame.render_widget(paragraph, inner);
+}
+
+/// Renders `text` as a line with a reversed-style block cursor at `cursor`
+/// (a char index), so the editable input boxes show a real terminal-style
+/// caret rather than a trailing marker glyph.
+fn input_line(text: &str, cursor: usize) -> Line<'static> {
+    let chars: Vec<char> = text.chars().collect();
+    let before: String = chars[..cursor.min(chars.len())].iter().collect();
+    let at_cursor = chars.get(cursor).copied();
+    let after: String = if cursor < chars.len() {
+        chars[cursor + 1..].iter().collect()
     } else {
-        Style::default().fg(Color::White)
+        String::new()
     };
 
-    let paragraph = Paragraph::new(Span::styled(display_text, text_style));
-    frame.render_widget(paragraph, inner);
+    let mut spans = vec![Span::styled(before, Style::default().fg(Color::White))];
+    spans.push(Span::styled(
+        at_cursor.map(String::from).unwrap_or_else(|| " ".to_string()),
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::REVERSED),
+    ));
+    if !after.is_empty() {
+        spans.push(Span::styled(after, Style::default().fg(Color::White)));
+    }
+
+    Line::from(spans)
 }
 
-fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+pub(super) fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let message = if let Some(ref status) = app.status {
         status.clone()
     } else {
         app.default_status()
     };
 
-    let spans = vec![
+    let tokens = app.estimated_tokens();
+    let budget_color = match budget_level(tokens, &app.model) {
+        BudgetLevel::Low => Color::Green,
+        BudgetLevel::Medium => Color::Yellow,
+        BudgetLevel::High => Color::Red,
+    };
+
+    let mut spans = vec![
         Span::styled(
             format!(" {} ", app.spinner_frame()),
             Style::default().fg(Color::Yellow),
@@ -247,9 +445,25 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(message, Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            format!("{} ", app.model),
+            Style::default().fg(Color::Gray),
+        ),
+        Span::styled(
+            format!("~{} tok ", tokens),
+            Style::default().fg(budget_color),
+        ),
     ];
 
+    if let Some(log_path) = &app.log_path {
+        spans.push(Span::styled(
+            format!("log:{} ", log_path),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    spans.push(Span::styled(message, Style::default().fg(Color::DarkGray)));
+
     let paragraph = Paragraph::new(Line::from(spans));
     frame.render_widget(paragraph, area);
 }